@@ -1,16 +1,20 @@
 use simd_ecs::prelude::*;
+use simd_ecs::hierarchy::Hierarchy;
 use physics::*;
 
 pub fn main() {
-    let mut world = World::default();
+    let mut world = World::new();
+    world.register::<System>();
+    world.register::<Body>();
+    world.register::<Colony>();
 
-    let sol = world.create_system(SystemRow {
+    let sol = world.create::<System>(SystemRow {
         name: "Sol".to_string(),
         star_radius: Length::in_meters(696340e3),
         star_temp: Temperature::in_kelvin(5778.0),
     });
 
-    let earth = world.create_planet(BodyRow {
+    let earth = create_planet(&world, BodyRow {
             name: "Earth".to_string(),
             mass: Default::default(),
             radius: Default::default(),
@@ -20,7 +24,7 @@ pub fn main() {
         },
         sol);
 
-    let _moon = world.create_body(BodyRow {
+    let _moon = create_body(&world, BodyRow {
         name: "Luna".to_string(),
         mass: Default::default(),
         radius: Default::default(),
@@ -31,65 +35,32 @@ pub fn main() {
     sol,
     Some(earth));
 
-    world.state.body.update_position(Time::zero());
+    world.table_mut::<Body>().rebuild_orbits();
+    world.table_mut::<Body>().update_position(Time::zero());
+    world.check_lengths().unwrap();
 }
 
-#[derive(Debug, Default, Clone)]
-pub struct World {
-    pub alloc: Allocators,
-    pub state: State,
-}
-
-impl World {
-    pub fn create_system(&mut self, system: SystemRow) -> Id<System> {
-        self.state.system.create(&mut self.alloc.system, system)
-    }
-
-    pub fn create_body<I: Into<BodyRow>>(&mut self, body: I, system: Id<System>, parent: Option<Id<Body>>) -> Id<Body> {
-        self.state.body.create(&mut self.alloc.body, body.into(), system, parent)
-    }
-
-    pub fn create_planet(&mut self, body: BodyRow, system: Id<System>) -> Id<Body> {
-        self.create_body(body, system, None)
-    }
+pub fn create_body<I: Into<BodyRow>>(world: &World, body: I, system: Id<System>, parent: Option<Id<Body>>) -> Id<Body> {
+    world.table_mut::<Body>().create(&mut world.alloc_mut::<Body>(), body.into(), system, parent)
 }
 
-#[derive(Debug, Default, Clone)]
-pub struct Allocators {
-    pub system: FixedAllocator<System>,
-    pub body: FixedAllocator<Body>,
-    pub colony: DynamicAllocator<Colony>,
+pub fn create_planet(world: &World, body: BodyRow, system: Id<System>) -> Id<Body> {
+    create_body(world, body, system, None)
 }
 
-#[derive(Debug, Default, Clone)]
-pub struct State {
-    pub system: System,
-    pub body: Body,
-    pub colony: Colony,
+impl Entity for System {
+    type Allocator = FixedAllocator<Self>;
 }
 
-#[derive(Debug, Default, Clone)]
-pub struct System {
-    pub name: Comp1<Self, String>,
-    pub star_radius: Comp1<Self, Length>,
-    pub star_temp: Comp1<Self, Temperature>,
+impl Entity for Body {
+    type Allocator = FixedAllocator<Self>;
 }
 
-impl System {
-    pub fn create(&mut self, alloc: &mut FixedAllocator<Self>, row: SystemRow) -> Id<Self> {
-        let id = alloc.create();
-        self.insert(&id, row);
-        id
-    }
-
-    fn insert(&mut self, id: &Id<Self>, row: SystemRow) {
-        self.name.insert(id, row.name);
-        self.star_radius.insert(id, row.star_radius);
-        self.star_temp.insert(id, row.star_temp);
-    }
+impl Entity for Colony {
+    type Allocator = DynamicAllocator<Self>;
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Table)]
 pub struct SystemRow {
     pub name: String,
     pub star_radius: Length,
@@ -102,13 +73,15 @@ pub struct Body {
     pub mass: Comp1<Self, Mass>,
     pub radius: Comp1<Self, Length>,
     pub orbit_parent: Comp1<Self, Option<Id<Self>>>,
+    pub orbit_order: Hierarchy<Self>,
     pub orbit_period: Comp1<Self, Time>,
-    pub orbit_radius: Comp1<Self, Length>,
     pub orbit_offset: Comp1<Self, Angle>,
 
-    pub position: Comp2<Self, Length, Length>,
+    // In meters, so that the position update runs through the column kernels.
+    pub orbit_radius: Comp1<Self, f64>,
+    pub position: Comp2<Self, f64, f64>,
     pub velocity: Comp2<Self, Speed, Speed>,
-    pub parent_position: Comp2<Self, Length, Length>,
+    pub parent_position: Comp2<Self, f64, f64>,
 
     pub system: Comp1<Self, Id<System>>,
 }
@@ -116,21 +89,12 @@ pub struct Body {
 impl Body {
     pub fn create(&mut self, alloc: &mut FixedAllocator<Self>, row: BodyRow, system: Id<System>, parent: Option<Id<Self>>) -> Id<Self> {
         let id = alloc.create();
-        self.insert(&id, row);
+        self.insert_row(id, row);
         self.link_system(&id, system);
         self.link_parent(&id, parent);
         id
     }
 
-    fn insert(&mut self, id: &Id<Self>, row: BodyRow) {
-        self.name.insert(id, row.name);
-        self.mass.insert(id, row.mass);
-        self.radius.insert(id, row.radius);
-        self.orbit_period.insert(id, row.orbit_period);
-        self.orbit_radius.insert(id, row.orbit_radius);
-        self.orbit_offset.insert(id, row.orbit_offset);
-    }
-
     fn link_system(&mut self, body: &Id<Body>, system: Id<System>) {
         self.system.insert(body, system);
     }
@@ -139,55 +103,72 @@ impl Body {
         self.orbit_parent.insert(body, parent);
     }
 
+    /// Re-sorts the orbit hierarchy. Call once after a batch of bodies has been created.
+    pub fn rebuild_orbits(&mut self) {
+        self.orbit_order.rebuild(&self.orbit_parent).expect("orbit parents form a cycle");
+    }
+
     pub fn update_position(&mut self, time: Time) {
         self.calculate_relative_position(time);
         self.update_parent_position();
         self.calculate_absolute_position();
     }
 
+    /// Rotates `(0, orbit_radius)` by each body's orbit angle. Only the trig is done per body.
     fn calculate_relative_position(&mut self, time: Time) {
-        self.position.iter_mut()
-            .zip(self.orbit_period.iter())
-            .zip(self.orbit_radius.iter())
-            .zip(self.orbit_offset.iter())
-            .for_each(|((((x, y), period), radius), offset)| {
-                let fraction = time / *period;
-                let angle = Angle::in_degrees(360.0) * fraction + *offset;
-                let cos = angle.cos();
-                let sin = angle.sin();
-                let x0 = Length::zero();
-                let y0 = *radius;
-
-                *x = x0 * cos - y0 * sin;
-                *y = x0 * sin + y0 * cos;
+        let (x, y) = (&mut self.position.0.values, &mut self.position.1.values);
+        x.clear();
+        y.clear();
+        query!(&self.orbit_period, &self.orbit_offset)
+            .for_each(|(period, offset)| {
+                let angle = Angle::in_degrees(360.0) * (time / *period) + *offset;
+                x.push(-angle.sin());
+                y.push(angle.cos());
             });
+
+        self.position.0.mul_assign(&self.orbit_radius);
+        self.position.1.mul_assign(&self.orbit_radius);
     }
 
     fn update_parent_position(&mut self) {
-        let position = &self.position;
-        let orbit_parent = &self.orbit_parent;
-
-        self.parent_position.iter_mut()
-            .zip(orbit_parent.iter())
-            .for_each(|((x, y), parent)| {
-                *x = Length::zero();
-                *y = Length::zero();
-                let mut parent = *parent;
-                while let Some((parent_x, parent_y)) = position.get(parent) {
-                    *x += *parent_x;
-                    *y += *parent_y;
-                    parent = orbit_parent.get(parent).copied().flatten();
-                }
-            });
+        self.orbit_order.accumulate_ancestors(&self.orbit_parent, &self.position.0, &mut self.parent_position.0);
+        self.orbit_order.accumulate_ancestors(&self.orbit_parent, &self.position.1, &mut self.parent_position.1);
     }
 
     fn calculate_absolute_position(&mut self) {
-        self.position.iter_mut()
-            .zip(self.parent_position.iter())
-            .for_each(|((x, y), (px, py))| {
-                *x = *px;
-                *y = *py;
-            });
+        self.position.add_assign(&self.parent_position);
+    }
+}
+
+impl Table for Body {
+    type Row = BodyRow;
+
+    fn insert_row<I: Indexes<Self>>(&mut self, id: I, row: BodyRow) {
+        self.name.insert(id, row.name);
+        self.mass.insert(id, row.mass);
+        self.radius.insert(id, row.radius);
+        self.orbit_period.insert(id, row.orbit_period);
+        self.orbit_radius.insert(id, row.orbit_radius / Length::in_meters(1.0));
+        self.orbit_offset.insert(id, row.orbit_offset);
+        self.position.insert(id, (0.0, 0.0));
+        self.velocity.insert(id, (Speed::zero(), Speed::zero()));
+        self.parent_position.insert(id, (0.0, 0.0));
+    }
+
+    fn column_lengths(&self) -> Vec<(&'static str, usize)> {
+        vec![
+            ("name", self.name.len()),
+            ("mass", self.mass.len()),
+            ("radius", self.radius.len()),
+            ("orbit_parent", self.orbit_parent.len()),
+            ("orbit_period", self.orbit_period.len()),
+            ("orbit_offset", self.orbit_offset.len()),
+            ("orbit_radius", self.orbit_radius.len()),
+            ("position", self.position.len()),
+            ("velocity", self.velocity.len()),
+            ("parent_position", self.parent_position.len()),
+            ("system", self.system.len()),
+        ]
     }
 }
 
@@ -213,19 +194,32 @@ pub struct Colony {
 impl Colony {
     pub fn create(&mut self, alloc: &mut DynamicAllocator<Self>, row: ColonyRow, body: Id<Body>) -> GenId<Self> {
         let id = alloc.create();
-        self.insert(&id, row);
+        self.insert_row(&id, row);
         self.link(&id, body);
         id
     }
 
-    fn insert(&mut self, id: &GenId<Self>, row: ColonyRow) {
+    fn link(&mut self, id: &GenId<Self>, body: Id<Body>) {
+        self.body.insert(id, body);
+    }
+}
+
+impl Table for Colony {
+    type Row = ColonyRow;
+
+    fn insert_row<I: Indexes<Self>>(&mut self, id: I, row: ColonyRow) {
         self.name.insert(id, row.name);
         self.population.insert(id, row.population);
         self.growth_rate.insert(id, row.growth_rate);
     }
 
-    fn link(&mut self, id: &GenId<Self>, body: Id<Body>) {
-        self.body.insert(id, body);
+    fn column_lengths(&self) -> Vec<(&'static str, usize)> {
+        vec![
+            ("name", self.name.len()),
+            ("population", self.population.len()),
+            ("growth_rate", self.growth_rate.len()),
+            ("body", self.body.len()),
+        ]
     }
 }
 
@@ -0,0 +1,269 @@
+use proc_macro::TokenStream;
+use proc_macro2::{Ident, Span};
+use quote::{format_ident, quote};
+use syn::punctuated::Punctuated;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, Token};
+
+/// Generates a struct-of-arrays table from a row struct.
+///
+/// The table is named by `#[table(Name)]`, or by dropping the `Row` suffix from the row type.
+/// `#[table(clone)]` makes the table `Clone` and implements `Table::clone_row`, both of which need
+/// every field to be `Clone`.
+/// The table and its row views are `Debug` when every field is, and `Default` in any case.
+/// Fields marked `#[table(link)]` hold ids of the same table and are remapped when cloning.
+#[proc_macro_derive(Table, attributes(table))]
+pub fn derive_table(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match table(input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn table(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let row = &input.ident;
+    let vis = &input.vis;
+
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(&input.generics, "Table rows cannot be generic"));
+    }
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => return Err(syn::Error::new_spanned(row, "Table rows must have named fields")),
+        },
+        _ => return Err(syn::Error::new_spanned(row, "Table rows must be structs")),
+    };
+
+    if fields.is_empty() {
+        return Err(syn::Error::new_spanned(row, "Table rows must have at least one field"));
+    }
+
+    let (table, clone) = table_attrs(&input)?;
+    let row_ref = format_ident!("{}Ref", row);
+    let row_mut = format_ident!("{}Mut", row);
+
+    let names = fields.iter().map(|f| f.ident.as_ref().unwrap()).collect::<Vec<_>>();
+    let vises = fields.iter().map(|f| &f.vis).collect::<Vec<_>>();
+    let types = fields.iter().map(|f| &f.ty).collect::<Vec<_>>();
+
+    let mut links = vec![];
+    let mut link_types = vec![];
+    for field in fields {
+        if is_link(field)? {
+            links.push(field.ident.as_ref().unwrap());
+            link_types.push(&field.ty);
+        }
+    }
+
+    let iter = nested_zip(&names, quote!(iter));
+    let pattern = nested_pattern(&names);
+
+    let derive_clone = if clone { quote!(Clone) } else { quote!() };
+    let clone_row = if clone {
+        quote! {
+            fn clone_row(&self, id: ::simd_ecs::allocators::Id<Self>) -> Option<#row> {
+                Some(#row {
+                    #(#names: ::std::clone::Clone::clone(::simd_ecs::Get1::get(&self.#names, id)?),)*
+                })
+            }
+        }
+    } else {
+        quote!()
+    };
+
+    let debug_table = debug_impl(quote!(#table), quote!(), &table, &names, &types);
+    let debug_ref = debug_impl(quote!(#row_ref<'a>), quote!(<'a>), &row_ref, &names, &types);
+    let debug_mut = debug_impl(quote!(#row_mut<'a>), quote!(<'a>), &row_mut, &names, &types);
+
+    Ok(quote! {
+        #[derive(Default, #derive_clone)]
+        #vis struct #table {
+            #(#vises #names: ::simd_ecs::components::Comp1<#table, #types>,)*
+        }
+
+        #[derive(Copy, Clone)]
+        #vis struct #row_ref<'a> {
+            #(#vises #names: &'a #types,)*
+        }
+
+        #vis struct #row_mut<'a> {
+            #(#vises #names: &'a mut #types,)*
+        }
+
+        #debug_table
+        #debug_ref
+        #debug_mut
+
+        impl #table {
+            pub fn create<A: ::simd_ecs::allocators::Allocator<Self>>(&mut self, alloc: &mut A, row: #row) -> A::Id {
+                let id = alloc.create();
+                let index = alloc.row(&id).expect("newly created id is alive");
+                self.insert(index, row);
+                id
+            }
+
+            pub fn insert<I: ::simd_ecs::allocators::Indexes<Self>>(&mut self, id: I, row: #row) {
+                #(::simd_ecs::Insert::insert(&mut self.#names, id, row.#names);)*
+            }
+
+            pub fn get_row(&self, id: ::simd_ecs::allocators::Id<Self>) -> Option<#row_ref<'_>> {
+                Some(#row_ref {
+                    #(#names: ::simd_ecs::Get1::get(&self.#names, id)?,)*
+                })
+            }
+
+            pub fn get_row_mut(&mut self, id: ::simd_ecs::allocators::Id<Self>) -> Option<#row_mut<'_>> {
+                Some(#row_mut {
+                    #(#names: ::simd_ecs::Get1::get_mut(&mut self.#names, id)?,)*
+                })
+            }
+
+            /// The row of `id`, looked up through the allocator so that generational and compacted
+            /// ids resolve to the right row.
+            pub fn get_row_for<A: ::simd_ecs::allocators::Allocator<Self>>(&self, alloc: &A, id: &A::Id) -> Option<#row_ref<'_>> {
+                self.get_row(alloc.row(id)?)
+            }
+
+            pub fn get_row_mut_for<A: ::simd_ecs::allocators::Allocator<Self>>(&mut self, alloc: &A, id: &A::Id) -> Option<#row_mut<'_>> {
+                self.get_row_mut(alloc.row(id)?)
+            }
+
+            pub fn iter_rows(&self) -> impl Iterator<Item = #row_ref<'_>> {
+                #iter.map(|#pattern| #row_ref { #(#names,)* })
+            }
+
+            pub fn iter_rows_with_ids<'a, A: ::simd_ecs::allocators::Allocator<Self>>(&'a self, alloc: &'a A) -> impl Iterator<Item = (A::Id, #row_ref<'a>)> + 'a {
+                self.iter_rows()
+                    .enumerate()
+                    .filter_map(move |(i, row)| alloc.live_id(i).map(|id| (id, row)))
+            }
+        }
+
+        impl ::simd_ecs::Table for #table {
+            type Row = #row;
+
+            fn insert_row<I: ::simd_ecs::allocators::Indexes<Self>>(&mut self, id: I, row: #row) {
+                self.insert(id, row);
+            }
+
+            #clone_row
+
+            fn column_lengths(&self) -> Vec<(&'static str, usize)> {
+                vec![#((stringify!(#names), self.#names.len()),)*]
+            }
+        }
+
+        impl<I> ::simd_ecs::prefab::Remap<I> for #row
+        where
+            #(#link_types: ::simd_ecs::prefab::Remap<I>,)*
+        {
+            fn remap(&mut self, _map: &::simd_ecs::prefab::IdMap<I>) {
+                #(::simd_ecs::prefab::Remap::remap(&mut self.#links, _map);)*
+            }
+        }
+
+        impl ::simd_ecs::Remove<::simd_ecs::allocators::DenseRow<#table>> for #table {
+            fn remove(&mut self, removed: ::simd_ecs::allocators::DenseRow<#table>) {
+                #(::simd_ecs::Remove::remove(&mut self.#names, removed);)*
+            }
+        }
+    })
+}
+
+/// The table name and whether `#[table(clone)]` was given.
+fn table_attrs(input: &DeriveInput) -> syn::Result<(Ident, bool)> {
+    let mut name = None;
+    let mut clone = false;
+    for attr in &input.attrs {
+        if attr.path().is_ident("table") {
+            for arg in attr.parse_args_with(Punctuated::<Ident, Token![,]>::parse_terminated)? {
+                if arg == "clone" {
+                    clone = true;
+                } else if name.is_some() {
+                    return Err(syn::Error::new_spanned(arg, "the table is already named"));
+                } else {
+                    name = Some(arg);
+                }
+            }
+        }
+    }
+
+    if let Some(name) = name {
+        return Ok((name, clone));
+    }
+
+    let row = input.ident.to_string();
+    match row.strip_suffix("Row") {
+        Some(name) if !name.is_empty() => Ok((Ident::new(name, Span::call_site()), clone)),
+        _ => Err(syn::Error::new_spanned(
+            &input.ident,
+            "name the table with #[table(Name)] or end the row type with `Row`",
+        )),
+    }
+}
+
+fn is_link(field: &syn::Field) -> syn::Result<bool> {
+    let mut link = false;
+    for attr in &field.attrs {
+        if attr.path().is_ident("table") {
+            let arg = attr.parse_args::<Ident>()?;
+            if arg != "link" {
+                return Err(syn::Error::new_spanned(arg, "expected #[table(link)]"));
+            }
+            link = true;
+        }
+    }
+    Ok(link)
+}
+
+/// `Debug` for a generated struct, which only applies when every field type of the row is `Debug`.
+///
+/// The bounds are higher-ranked so that they are checked where the impl is used rather than
+/// rejected up front for rows whose fields are not `Debug`.
+fn debug_impl(
+    ty: proc_macro2::TokenStream,
+    generics: proc_macro2::TokenStream,
+    name: &Ident,
+    names: &[&Ident],
+    types: &[&syn::Type],
+) -> proc_macro2::TokenStream {
+    quote! {
+        impl #generics ::std::fmt::Debug for #ty
+        where
+            #(for<'__debug> #types: ::std::fmt::Debug,)*
+        {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                f.debug_struct(stringify!(#name))
+                    #(.field(stringify!(#names), &self.#names))*
+                    .finish()
+            }
+        }
+    }
+}
+
+/// `self.a.iter().zip(self.b.iter().zip(self.c.iter()))`
+fn nested_zip(names: &[&Ident], method: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+    match names.split_first() {
+        Some((first, [])) => quote!(self.#first.#method()),
+        Some((first, rest)) => {
+            let rest = nested_zip(rest, method.clone());
+            quote!(self.#first.#method().zip(#rest))
+        }
+        None => unreachable!("rows have at least one field"),
+    }
+}
+
+/// `(a, (b, c))`
+fn nested_pattern(names: &[&Ident]) -> proc_macro2::TokenStream {
+    match names.split_first() {
+        Some((first, [])) => quote!(#first),
+        Some((first, rest)) => {
+            let rest = nested_pattern(rest);
+            quote!((#first, #rest))
+        }
+        None => quote!(()),
+    }
+}
@@ -0,0 +1,89 @@
+use std::fmt;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+static NEXT_BRAND: AtomicU32 = AtomicU32::new(1);
+
+/// Tags ids with the allocator instance that created them, so that ids from one world cannot
+/// index the columns of another. Checked in debug builds.
+///
+/// Ids made outside an allocator, and columns that have not been inserted into yet, carry
+/// `Brand::NONE` and match any brand. Release builds carry no brands and `brand()` is always
+/// `Brand::NONE`.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Brand(u32);
+
+impl Brand {
+    pub const NONE: Brand = Brand(0);
+
+    /// A brand that no other allocator has.
+    pub fn new() -> Self {
+        Brand(NEXT_BRAND.fetch_add(1, Ordering::Relaxed))
+    }
+
+    /// Panics if both brands are set and differ.
+    pub fn check(self, id: Brand) {
+        self.check_for(id, "");
+    }
+
+    /// Like `check`, naming the entity type `for_type` of the column in the panic message.
+    fn check_for(self, id: Brand, for_type: &str) {
+        if self != Brand::NONE && id != Brand::NONE && self != id {
+            panic!("id from {} used with a column of {}{}", id, self, for_type);
+        }
+    }
+}
+
+impl fmt::Display for Brand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "allocator #{}", self.0)
+    }
+}
+
+/// The brand owned by an allocator. A clone is a separate allocator, so it draws a new brand.
+#[cfg(debug_assertions)]
+#[derive(Debug)]
+pub(crate) struct OwnBrand(pub(crate) Brand);
+
+#[cfg(debug_assertions)]
+impl Default for OwnBrand {
+    fn default() -> Self {
+        OwnBrand(Brand::new())
+    }
+}
+
+#[cfg(debug_assertions)]
+impl Clone for OwnBrand {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+/// The brand a column is bound to by the first branded id written to it. A clone starts unbound,
+/// since it is usually paired with a clone of the allocator.
+#[cfg(debug_assertions)]
+#[derive(Debug, Default)]
+pub(crate) struct ColumnBrand(Brand);
+
+#[cfg(debug_assertions)]
+impl Clone for ColumnBrand {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+#[cfg(debug_assertions)]
+impl ColumnBrand {
+    pub(crate) fn bind<ID>(&mut self, id: Brand) {
+        if self.0 == Brand::NONE {
+            self.0 = id;
+        }
+        self.check::<ID>(id);
+    }
+
+    /// Panics naming the column's entity type `ID` as well as both allocators.
+    pub(crate) fn check<ID>(&self, id: Brand) {
+        if self.0 != Brand::NONE && id != Brand::NONE && self.0 != id {
+            self.0.check_for(id, &format!(" for `{}`", std::any::type_name::<ID>()));
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use crate::allocators::Id;
+use std::fmt;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Maps allocator slots to densely packed column rows and back.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(bound = ""))]
+pub struct DenseMap<T> {
+    rows: Vec<Option<Id<T>>>,
+    slots: Vec<Id<T>>,
+}
+
+/// A row vacated by a compacting allocator, which each column fills by moving its last row in.
+///
+/// Only `DynamicAllocator::kill_and_remove` hands these out, so `Remove` can only move rows that
+/// the allocator has already renumbered.
+pub struct DenseRow<T> {
+    row: Id<T>,
+    last: Id<T>,
+}
+
+impl<T> DenseRow<T> {
+    pub(crate) fn new(row: Id<T>, last: Id<T>) -> Self {
+        Self { row, last }
+    }
+
+    /// The vacated row.
+    pub fn row(&self) -> Id<T> {
+        self.row
+    }
+
+    /// The table's last row before the removal, which is moved into `row` unless they are equal.
+    pub fn last(&self) -> Id<T> {
+        self.last
+    }
+}
+
+impl<T> Copy for DenseRow<T> {}
+
+impl<T> Clone for DenseRow<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> PartialEq for DenseRow<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.row == other.row && self.last == other.last
+    }
+}
+
+impl<T> Eq for DenseRow<T> {}
+
+impl<T> fmt::Debug for DenseRow<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DenseRow")
+            .field("row", &self.row.index)
+            .field("last", &self.last.index)
+            .finish()
+    }
+}
+
+impl<T> Default for DenseMap<T> {
+    fn default() -> Self {
+        Self {
+            rows: Default::default(),
+            slots: Default::default(),
+        }
+    }
+}
+
+impl<T> DenseMap<T> {
+    pub fn row(&self, slot: Id<T>) -> Option<Id<T>> {
+        self.rows.get(slot.index).copied().flatten()
+    }
+
+    pub fn slot(&self, row: Id<T>) -> Option<Id<T>> {
+        self.slots.get(row.index).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    pub(crate) fn push(&mut self, slot: Id<T>) -> Id<T> {
+        let row = Id::new(self.slots.len());
+
+        if self.rows.len() <= slot.index {
+            self.rows.resize(slot.index + 1, None);
+        }
+        self.rows[slot.index] = Some(row);
+        self.slots.push(slot);
+
+        row
+    }
+
+    /// Returns the row that was vacated. The last row is moved into it, matching `Remove`.
+    pub(crate) fn swap_remove(&mut self, slot: Id<T>) -> Option<DenseRow<T>> {
+        let row = self.rows.get_mut(slot.index)?.take()?;
+        let last = Id::new(self.slots.len() - 1);
+
+        self.slots.swap_remove(row.index);
+        if let Some(moved) = self.slots.get(row.index) {
+            self.rows[moved.index] = Some(row);
+        }
+
+        Some(DenseRow::new(row, last))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn swap_remove_moves_last_row() {
+        let mut map = DenseMap::<()>::default();
+
+        let rows = (0..3).map(|i| map.push(Id::new(i))).collect::<Vec<_>>();
+        assert_eq!(vec![Id::new(0), Id::new(1), Id::new(2)], rows);
+
+        assert_eq!(Some(DenseRow::new(Id::new(0), Id::new(2))), map.swap_remove(Id::new(0)));
+        assert_eq!(None, map.row(Id::new(0)));
+        assert_eq!(Some(Id::new(0)), map.row(Id::new(2)));
+        assert_eq!(Some(Id::new(2)), map.slot(Id::new(0)));
+        assert_eq!(2, map.len());
+
+        assert_eq!(None, map.swap_remove(Id::new(0)));
+    }
+}
@@ -1,70 +1,348 @@
-use std::num::NonZeroU32;
-use crate::allocators::{Indexes, Id};
+#[cfg(feature = "branded")]
+use crate::allocators::Brand;
+#[cfg(all(feature = "branded", debug_assertions))]
+use crate::allocators::OwnBrand;
+use crate::allocators::{Allocator, DenseMap, DenseRow, EventCursor, EventQueue, Gen, GenOverflow, Generation, Indexes, Id, Lagged, Reserved};
+use crate::bitset::BitSet;
 use crate::links::GenIds;
 use crate::components::Comp1;
-use crate::{Get1, Insert};
+use crate::{Get1, Insert, Remove};
 use std::cmp::Ordering;
+use std::fmt;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Default, Clone)]
-pub struct DynamicAllocator<T> {
-    pub(crate) gen: Comp1<T, Gen>,
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(bound = ""))]
+pub struct DynamicAllocator<T, G: Generation = u32> {
+    pub(crate) gen: Comp1<T, Gen<G>>,
     dead: Vec<Id<T>>,
+    living: BitSet,
+    dense: Option<DenseMap<T>>,
+    overflow: GenOverflow,
+    retired: usize,
+    record_events: bool,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    reserved: Reserved,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    created: EventQueue<GenId<T, G>>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    killed: EventQueue<GenId<T, G>>,
+    #[cfg(all(feature = "branded", debug_assertions))]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    brand: OwnBrand,
     pub(crate) version: u64,
 }
 
-impl<T> DynamicAllocator<T> {
-    pub fn create(&mut self) -> GenId<T> {
-        if let Some(index) = self.dead.pop() {
+impl<T, G: Generation> Default for DynamicAllocator<T, G> {
+    fn default() -> Self {
+        Self {
+            gen: Default::default(),
+            dead: Default::default(),
+            living: Default::default(),
+            dense: Default::default(),
+            overflow: Default::default(),
+            retired: Default::default(),
+            record_events: Default::default(),
+            reserved: Default::default(),
+            created: Default::default(),
+            killed: Default::default(),
+            #[cfg(all(feature = "branded", debug_assertions))]
+            brand: Default::default(),
+            version: Default::default(),
+        }
+    }
+}
+
+impl<T, G: Generation> DynamicAllocator<T, G> {
+    /// Creates an allocator whose components are kept densely packed: killing an entity through
+    /// `kill_and_remove` swap-removes its row, and columns are indexed by `row` rather than by slot.
+    pub fn compacting() -> Self {
+        Self {
+            dense: Some(Default::default()),
+            ..Default::default()
+        }
+    }
+
+    #[cfg(feature = "branded")]
+    pub fn brand(&self) -> Brand {
+        #[cfg(debug_assertions)]
+        return self.brand.0;
+        #[cfg(not(debug_assertions))]
+        Brand::NONE
+    }
+
+    fn tag(&self, id: Id<T>) -> Id<T> {
+        #[cfg(all(feature = "branded", debug_assertions))]
+        let id = id.with_brand(self.brand.0);
+        id
+    }
+
+    pub fn with_overflow(mut self, overflow: GenOverflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    /// Records created and killed ids so they can be read with `drain_created` and `drain_killed`.
+    pub fn with_events(mut self) -> Self {
+        self.record_events = true;
+        self
+    }
+
+    pub fn create(&mut self) -> GenId<T, G> {
+        self.flush();
+        self.create_slot()
+    }
+
+    fn create_slot(&mut self) -> GenId<T, G> {
+        let id = if let Some(index) = self.dead.pop() {
             let gen = self.gen.get(index).copied().unwrap_or_default();
-            GenId::new(index, gen)
+            self.living.insert(index.index);
+            GenId::new(self.tag(index), gen)
         } else {
             let index = Id::new(self.gen.len());
             let gen = Gen::default();
 
             self.gen.insert(index, gen);
+            self.living.insert(index.index);
+
+            GenId::new(self.tag(index), gen)
+        };
+
+        if let Some(dense) = &mut self.dense {
+            dense.push(id.index);
+        }
+
+        if self.record_events {
+            self.created.push(id);
+        }
+
+        id
+    }
 
-            GenId::new(index, gen)
+    /// Creates `n` entities, reusing dead slots before growing.
+    pub fn create_many(&mut self, n: usize) -> Vec<GenId<T, G>> {
+        self.flush();
+        let fresh = n.saturating_sub(self.dead.len());
+        self.gen.values.reserve(fresh);
+        (0..n).map(|_| self.create()).collect()
+    }
+
+    /// Panics for a compacting allocator, whose columns must be compacted with `kill_and_remove`.
+    pub fn kill(&mut self, id: GenId<T, G>) {
+        assert!(self.dense.is_none(), "use kill_and_remove with a compacting allocator");
+        self.kill_slot(id);
+    }
+
+    /// Kills the entity and, for a compacting allocator, removes its row from `columns`.
+    pub fn kill_and_remove<C: Remove<DenseRow<T>>>(&mut self, id: GenId<T, G>, columns: &mut C) {
+        if self.is_valid(&id) {
+            if let Some(removed) = self.dense.as_mut().and_then(|dense| dense.swap_remove(id.index)) {
+                columns.remove(DenseRow::new(self.tag(removed.row()), self.tag(removed.last())));
+            }
         }
+        self.kill_slot(id);
     }
 
-    pub fn kill(&mut self, id: GenId<T>) {
+    fn kill_slot(&mut self, id: GenId<T, G>) {
+        self.flush();
         if self.is_valid(&id) {
             if let Some(gen) = self.gen.get_mut(id.index) {
-                *gen = gen.next();
-                self.dead.push(id.index);
+                self.living.remove(id.index.index);
+
+                match (gen.next(), self.overflow) {
+                    (Some(next), _) => {
+                        *gen = next;
+                        self.dead.push(id.index);
+                    }
+                    (None, GenOverflow::Wrap) => {
+                        *gen = Gen::default();
+                        self.dead.push(id.index);
+                    }
+                    (None, GenOverflow::Retire) => self.retired += 1,
+                }
+
+                if self.record_events {
+                    self.killed.push(id);
+                }
             }
         }
         self.version += 1;
     }
 
-    pub fn is_valid(&self, id: &GenId<T>) -> bool {
-        self.gen
-            .get(id.index)
-            .map(|gen| *gen == id.gen)
-            .unwrap_or(false)
+    /// The slot of a live id, or which generation was expected and which one the slot holds now.
+    pub fn check(&self, id: GenId<T, G>) -> Result<Id<T>, StaleId<G>> {
+        if self.is_valid(&id) {
+            return Ok(id.index);
+        }
+
+        let current = self.gen.get(id.index).copied().filter(|_| self.living.get(id.index.index));
+        Err(StaleId {
+            index: id.index.index,
+            expected: id.gen,
+            current,
+        })
+    }
+
+    pub fn is_valid(&self, id: &GenId<T, G>) -> bool {
+        self.is_alive(id.index, id.gen)
+    }
+
+    pub fn is_alive(&self, id: Id<T>, gen: Gen<G>) -> bool {
+        self.living.get(id.index)
+            && self.gen
+                .get(id)
+                .map(|live| *live == gen)
+                .unwrap_or(false)
+    }
+
+    /// The ids created since the cursor's last read.
+    pub fn drain_created(&self, cursor: &mut EventCursor) -> Result<&[GenId<T, G>], Lagged> {
+        self.created.read(cursor)
+    }
+
+    /// The ids killed since the cursor's last read.
+    pub fn drain_killed(&self, cursor: &mut EventCursor) -> Result<&[GenId<T, G>], Lagged> {
+        self.killed.read(cursor)
+    }
+
+    pub fn created(&self) -> &EventQueue<GenId<T, G>> {
+        &self.created
+    }
+
+    pub fn killed(&self) -> &EventQueue<GenId<T, G>> {
+        &self.killed
+    }
+
+    pub fn records_events(&self) -> bool {
+        self.record_events
+    }
+
+    /// Drops every recorded event immediately. Readers that had not read them yet get `Lagged` on
+    /// their next drain, so call this only after every reader has caught up.
+    pub fn clear_events(&mut self) {
+        self.created.clear();
+        self.killed.clear();
     }
 
-    pub fn is_alive(&self, id: Id<T>, gen: Gen) -> bool {
-        self.gen
-            .get(id)
-            .map(|live| *live == gen)
-            .unwrap_or(false)
+    pub fn living(&self) -> &BitSet {
+        &self.living
     }
 
-    pub fn validate<'a, ID2>(&'a self, ids: &'a mut GenIds<ID2, T>) -> Valid<'a, ID2, T> {
-        ids.update(&self);
-        Valid::new(&ids.ids.0)
+    /// Number of slots that exhausted their generations and will not be reused.
+    pub fn retired(&self) -> usize {
+        self.retired
+    }
+
+    /// The column row holding the entity's components, if the id is still valid.
+    pub fn row(&self, id: &GenId<T, G>) -> Option<Id<T>> {
+        if !self.is_valid(id) {
+            return None;
+        }
+
+        match &self.dense {
+            Some(dense) => dense.row(id.index).map(|row| self.tag(row)),
+            None => Some(id.index),
+        }
+    }
+
+    pub fn dense(&self) -> Option<&DenseMap<T>> {
+        self.dense.as_ref()
+    }
+
+    /// Creates the reserved entities in the order they were reserved.
+    pub fn flush(&mut self) {
+        for _ in 0..self.reserved.take() {
+            self.create_slot();
+        }
+    }
+
+    /// The id `create` would return after `n` other creations, if nothing is killed in between.
+    fn nth_free(&self, n: usize) -> GenId<T, G> {
+        match self.dead.len().checked_sub(n + 1) {
+            Some(i) => {
+                let index = self.dead[i];
+                let gen = self.gen.get(index).copied().unwrap_or_default();
+                GenId::new(self.tag(index), gen)
+            }
+            None => {
+                let index = Id::new(self.gen.len() + n - self.dead.len());
+                GenId::new(self.tag(index), Gen::default())
+            }
+        }
+    }
+
+    pub fn validate<'a, ID2>(&'a self, ids: &'a mut GenIds<ID2, T, G>) -> Valid<'a, ID2, T> {
+        ids.update(self);
+        Valid::new(&ids.ids.0, self.dense.as_ref())
     }
 }
 
-#[derive(Debug)]
-pub struct GenId<T> {
+impl<T, G: Generation> Allocator<T> for DynamicAllocator<T, G> {
+    type Id = GenId<T, G>;
+
+    fn create(&mut self) -> GenId<T, G> {
+        DynamicAllocator::create(self)
+    }
+
+    fn row(&self, id: &GenId<T, G>) -> Option<Id<T>> {
+        DynamicAllocator::row(self, id)
+    }
+
+    fn is_alive_index(&self, index: usize) -> bool {
+        match &self.dense {
+            Some(dense) => index < dense.len(),
+            None => self.living.get(index),
+        }
+    }
+
+    fn live_id(&self, index: usize) -> Option<GenId<T, G>> {
+        let slot = match &self.dense {
+            Some(dense) => dense.slot(Id::new(index))?,
+            None if self.living.get(index) => Id::new(index),
+            None => return None,
+        };
+
+        self.gen.get(slot).map(|gen| GenId::new(self.tag(slot), *gen))
+    }
+
+    fn rows(&self) -> usize {
+        match &self.dense {
+            Some(dense) => dense.len(),
+            None => self.gen.len(),
+        }
+    }
+
+    fn peek(&self, n: usize) -> GenId<T, G> {
+        self.nth_free(self.reserved.get() + n)
+    }
+
+    fn reserve(&self) -> GenId<T, G> {
+        self.nth_free(self.reserved.next())
+    }
+
+    fn flush(&mut self) {
+        DynamicAllocator::flush(self)
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(bound = ""))]
+pub struct GenId<T, G: Generation = u32> {
     pub(crate) index: Id<T>,
-    pub(crate) gen: Gen,
+    pub(crate) gen: Gen<G>,
 }
 
-impl<T> GenId<T> {
-    fn new(index: Id<T>, gen: Gen) -> Self {
+impl<T, G: Generation> fmt::Debug for GenId<T, G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GenId")
+            .field("index", &self.index)
+            .field("gen", &self.gen)
+            .finish()
+    }
+}
+
+impl<T, G: Generation> GenId<T, G> {
+    pub(crate) fn new(index: Id<T>, gen: Gen<G>) -> Self {
         Self {
             index,
             gen,
@@ -72,77 +350,125 @@ impl<T> GenId<T> {
     }
 }
 
-impl<T> Clone for GenId<T> {
+impl<T, G: Generation> Clone for GenId<T, G> {
     fn clone(&self) -> Self {
         Self::new(self.index, self.gen)
     }
 }
 
-impl<T> Copy for GenId<T> {}
+impl<T, G: Generation> Copy for GenId<T, G> {}
 
-impl<T> PartialEq for GenId<T> {
+impl<T, G: Generation> PartialEq for GenId<T, G> {
     fn eq(&self, other: &Self) -> bool {
-        self.index.eq(&other.index)
+        (self.index, self.gen).eq(&(other.index, other.gen))
     }
 }
 
-impl<T> Eq for GenId<T> {}
+impl<T, G: Generation> Eq for GenId<T, G> {}
 
-impl<T> std::hash::Hash for GenId<T> {
+impl<T, G: Generation> std::hash::Hash for GenId<T, G> {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         (self.index, self.gen).hash(state)
     }
 }
 
-impl<T> PartialOrd for GenId<T> {
+impl<T, G: Generation> PartialOrd for GenId<T, G> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
     }
 }
 
-impl<T> Ord for GenId<T> {
+impl<T, G: Generation> Ord for GenId<T, G> {
     fn cmp(&self, other: &Self) -> Ordering {
         (self.index, self.gen).cmp(&(other.index, other.gen))
     }
 }
 
-impl<'a, T> Indexes<T> for GenId<T> {
-    fn index(&self) -> usize {
-        self.index.index
+/// A `GenId` whose entity has been killed.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct StaleId<G: Generation = u32> {
+    pub index: usize,
+    pub expected: Gen<G>,
+    /// The generation of the entity now living in the slot, if any.
+    pub current: Option<Gen<G>>,
+}
+
+impl<G: Generation> fmt::Display for StaleId<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.current {
+            Some(current) => write!(
+                f,
+                "stale id for slot {}: expected generation {}, slot is at generation {}",
+                self.index,
+                self.expected.get(),
+                current.get()
+            ),
+            None => write!(
+                f,
+                "stale id for slot {}: expected generation {}, slot is empty",
+                self.index,
+                self.expected.get()
+            ),
+        }
     }
 }
 
-impl<'a, T> Indexes<T> for &'a GenId<T> {
+impl<G: Generation> std::error::Error for StaleId<G> {}
+
+impl<T, G: Generation> Indexes<T> for GenId<T, G> {
     fn index(&self) -> usize {
         self.index.index
     }
-}
-
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
-pub struct Gen(NonZeroU32);
 
-impl Default for Gen {
-    fn default() -> Self {
-        Gen(NonZeroU32::new(1).unwrap())
+    #[cfg(all(feature = "branded", debug_assertions))]
+    fn brand(&self) -> Brand {
+        self.index.brand
     }
 }
 
-impl Gen {
-    pub fn next(self) -> Self {
-        let next = NonZeroU32::new(self.0.get() + 1).unwrap();
-        Self(next)
+impl<T, G: Generation> Indexes<T> for &GenId<T, G> {
+    fn index(&self) -> usize {
+        self.index.index
+    }
+
+    #[cfg(all(feature = "branded", debug_assertions))]
+    fn brand(&self) -> Brand {
+        self.index.brand
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug)]
 pub struct Valid<'a, FROM, TO> {
     pub ids: &'a Comp1<FROM, Option<Id<TO>>>,
+    dense: Option<&'a DenseMap<TO>>,
 }
 
+impl<'a, FROM, TO> Clone for Valid<'a, FROM, TO> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, FROM, TO> Copy for Valid<'a, FROM, TO> {}
+
 impl<'a, FROM, TO> Valid<'a, FROM, TO> {
-    fn new(ids: &'a Comp1<FROM, Option<Id<TO>>>) -> Self {
+    fn new(ids: &'a Comp1<FROM, Option<Id<TO>>>, dense: Option<&'a DenseMap<TO>>) -> Self {
         Valid {
             ids,
+            dense,
+        }
+    }
+
+    /// The rows of the target columns, resolved through the target's dense map when it is compacting.
+    pub fn iter(&self) -> impl Iterator<Item = Option<Id<TO>>> + 'a {
+        let valid = *self;
+        self.ids.iter().map(move |id| valid.resolve(*id))
+    }
+
+    pub fn resolve(&self, id: Option<Id<TO>>) -> Option<Id<TO>> {
+        match self.dense {
+            Some(dense) => id.and_then(|id| dense.row(id)),
+            None => id,
         }
     }
 }
@@ -170,6 +496,247 @@ mod tests {
         a.kill(id0);
         let id1 = a.create();
 
-        assert_eq!(GenId::new(Id::new(0), Gen::default().next()), id1);
+        assert_eq!(GenId::new(Id::new(0), Gen::default().next().unwrap()), id1);
+    }
+
+    #[test]
+    fn create_many_reuses_dead_slots_first() {
+        let mut a = DynamicAllocator::<()>::default();
+
+        let first = a.create_many(3);
+        a.kill(first[0]);
+        a.kill(first[2]);
+        let ids = a.create_many(3);
+
+        assert_eq!(vec![2, 0, 3], ids.iter().map(|id| id.index.index).collect::<Vec<_>>());
+        assert!(ids.iter().all(|id| a.is_valid(id)));
+        assert!(!a.is_valid(&first[0]));
+    }
+
+    #[test]
+    #[should_panic(expected = "use kill_and_remove")]
+    fn kill_panics_when_compacting() {
+        let mut a = DynamicAllocator::<()>::compacting();
+        let id = a.create();
+        a.kill(id);
+    }
+
+    #[test]
+    fn peek_predicts_created_ids() {
+        let mut a = DynamicAllocator::<()>::default();
+
+        let first = a.create_many(3);
+        a.kill(first[0]);
+        a.kill(first[1]);
+
+        let predicted = (0..3).map(|n| a.peek(n)).collect::<Vec<_>>();
+        assert_eq!(predicted, a.create_many(3));
+    }
+
+    #[test]
+    fn create_skips_reserved_ids() {
+        let mut a = DynamicAllocator::<()>::default();
+        let first = a.create_many(2);
+        a.kill(first[0]);
+
+        let reserved = [a.reserve(), a.reserve()];
+        assert_eq!(first[0].index, reserved[0].index);
+        assert!(!a.is_valid(&reserved[0]));
+
+        let created = a.create();
+        assert!(!reserved.contains(&created));
+        assert!(reserved.iter().all(|id| a.is_valid(id)));
+
+        a.kill(created);
+        let reserved = a.reserve();
+        a.kill(first[1]);
+        assert_eq!(created.index, reserved.index);
+        assert!(a.is_valid(&reserved));
+    }
+
+    #[test]
+    fn exhausted_slot_is_retired() {
+        let mut a = DynamicAllocator::<(), u16>::default();
+
+        let mut id = a.create();
+        for _ in 1..u16::MAX {
+            a.kill(id);
+            id = a.create();
+        }
+        assert_eq!(u16::MAX as u64, id.gen.get());
+        assert_eq!(0, a.retired());
+
+        a.kill(id);
+        assert!(!a.is_valid(&id));
+        assert_eq!(1, a.retired());
+
+        let fresh = a.create();
+        assert_eq!(Id::new(1), fresh.index);
+        assert!(!a.is_valid(&id));
+        assert!(!a.is_alive_index(0));
+    }
+
+    #[test]
+    fn exhausted_slot_wraps() {
+        let mut a = DynamicAllocator::<(), u16>::default().with_overflow(GenOverflow::Wrap);
+
+        let mut id = a.create();
+        for _ in 1..u16::MAX {
+            a.kill(id);
+            id = a.create();
+        }
+
+        a.kill(id);
+        let wrapped = a.create();
+        assert_eq!(Id::new(0), wrapped.index);
+        assert_eq!(1, wrapped.gen.get());
+        assert_eq!(0, a.retired());
+    }
+
+    #[test]
+    fn stale_id_is_not_equal_to_reused_id() {
+        use std::collections::HashMap;
+
+        let mut a = DynamicAllocator::<()>::default();
+
+        let stale = a.create();
+        a.kill(stale);
+        let live = a.create();
+
+        assert_eq!(stale.index, live.index);
+        assert_ne!(stale, live);
+
+        let mut map = HashMap::new();
+        map.insert(live, "live");
+        assert_eq!(None, map.get(&stale));
+        assert_eq!(Some(&"live"), map.get(&live));
+    }
+
+    #[test]
+    fn check_reports_generations() {
+        let mut a = DynamicAllocator::<()>::default();
+
+        let stale = a.create();
+        assert_eq!(Ok(Id::new(0)), a.check(stale));
+
+        a.kill(stale);
+        let error = a.check(stale).unwrap_err();
+        assert_eq!(StaleId { index: 0, expected: Gen::default(), current: None }, error);
+        assert_eq!("stale id for slot 0: expected generation 1, slot is empty", error.to_string());
+
+        let live = a.create();
+        let error = a.check(stale).unwrap_err();
+        assert_eq!(Some(live.gen), error.current);
+        assert_eq!("stale id for slot 0: expected generation 1, slot is at generation 2", error.to_string());
+    }
+
+    #[test]
+    fn events_record_created_and_killed() {
+        let mut a = DynamicAllocator::<()>::default().with_events();
+        let mut created = EventCursor::default();
+        let mut killed = EventCursor::default();
+
+        let id0 = a.create();
+        let id1 = a.create();
+        a.kill(id0);
+        a.kill(id0);
+
+        assert_eq!(Ok(&[id0, id1][..]), a.drain_created(&mut created));
+        assert_eq!(Ok(&[id0][..]), a.drain_killed(&mut killed));
+
+        a.clear_events();
+        let id2 = a.create();
+        assert_eq!(Ok(&[id2][..]), a.drain_created(&mut created));
+        assert_eq!(Ok(&[][..]), a.drain_killed(&mut killed));
+        assert_eq!(Err(Lagged { missed: 2 }), a.drain_created(&mut EventCursor::default()));
+    }
+
+    #[test]
+    fn living_tracks_create_and_kill() {
+        let mut a = DynamicAllocator::<()>::default();
+
+        let id0 = a.create();
+        let id1 = a.create();
+        a.kill(id0);
+
+        assert!(!a.is_alive_index(0));
+        assert!(a.is_alive_index(1));
+        assert_eq!(None, a.live_id(0));
+        assert_eq!(Some(id1), a.live_id(1));
+
+        let id2 = a.create();
+        assert!(a.is_alive_index(0));
+        assert_eq!(Some(id2), a.live_id(0));
+    }
+
+    #[test]
+    fn compacting_kill_and_remove() {
+        let mut a = DynamicAllocator::<()>::compacting();
+        let mut values = Comp1::<(), u32>::default();
+
+        let ids = (0..3).map(|i| {
+            let id = a.create();
+            values.insert(a.row(&id).unwrap(), i);
+            id
+        }).collect::<Vec<_>>();
+
+        a.kill_and_remove(ids[0], &mut values);
+
+        assert_eq!(vec![2, 1], values.values);
+        assert_eq!(None, a.row(&ids[0]));
+        assert_eq!(Some(Id::new(0)), a.row(&ids[2]));
+        assert_eq!(Some(Id::new(1)), a.row(&ids[1]));
+        assert_eq!(vec![(ids[2], &2), (ids[1], &1)], values.iter_with_ids(&a).collect::<Vec<_>>());
+
+        let id3 = a.create();
+        values.insert(a.row(&id3).unwrap(), 3);
+        assert_eq!(Some(Id::new(2)), a.row(&id3));
+        assert_eq!(vec![2, 1, 3], values.values);
+    }
+
+    #[test]
+    fn valid_resolves_compacted_rows() {
+        let mut a = DynamicAllocator::<()>::compacting();
+        let mut values = Comp1::<(), u32>::default();
+        let mut links = GenIds::<u8, ()>::default();
+
+        let id0 = a.create();
+        values.insert(a.row(&id0).unwrap(), 10);
+        let id1 = a.create();
+        values.insert(a.row(&id1).unwrap(), 20);
+
+        links.insert(Id::<u8>::new(0), id1);
+        links.insert(Id::<u8>::new(1), id0);
+
+        a.kill_and_remove(id0, &mut values);
+
+        let mut out = Comp1::<u8, u32>::default();
+        out.insert(Id::new(0), 0);
+        out.insert(Id::new(1), 0);
+        out.get_from_or(&values, &a.validate(&mut links), 99);
+
+        assert_eq!(vec![20, 99], out.values);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let mut a = DynamicAllocator::<()>::default();
+
+        let live = a.create();
+        let dead = a.create();
+        a.kill(dead);
+
+        let json = serde_json::to_string(&(&a, live, dead)).unwrap();
+        let (mut b, live, dead): (DynamicAllocator<()>, GenId<()>, GenId<()>) = serde_json::from_str(&json).unwrap();
+
+        assert!(b.is_valid(&live));
+        assert!(!b.is_valid(&dead));
+        assert_eq!(a.version, b.version);
+
+        let reused = b.create();
+        assert_eq!(dead.index, reused.index);
+        assert!(!b.is_valid(&dead));
+        assert!(b.is_valid(&reused));
     }
 }
@@ -0,0 +1,131 @@
+use std::fmt;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// An append-only log of events that any number of readers can consume at their own pace.
+///
+/// Each reader keeps an `EventCursor`. Events stay readable until `clear` is called, after which
+/// readers that had not caught up get a `Lagged` error.
+#[derive(Debug, Clone)]
+pub struct EventQueue<E> {
+    events: Vec<E>,
+    start: u64,
+}
+
+/// A reader's position in an `EventQueue`.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct EventCursor {
+    next: u64,
+}
+
+/// The reader's cursor pointed at events that have already been cleared.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Lagged {
+    pub missed: u64,
+}
+
+impl fmt::Display for Lagged {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "event reader missed {} cleared events", self.missed)
+    }
+}
+
+impl std::error::Error for Lagged {}
+
+impl<E> Default for EventQueue<E> {
+    fn default() -> Self {
+        Self {
+            events: Default::default(),
+            start: 0,
+        }
+    }
+}
+
+impl<E> EventQueue<E> {
+    pub(crate) fn push(&mut self, event: E) {
+        self.events.push(event);
+    }
+
+    fn end(&self) -> u64 {
+        self.start + self.events.len() as u64
+    }
+
+    /// The events since the cursor's last read. On `Lagged` the cursor is moved to the oldest
+    /// retained event, so the next read returns what is still available.
+    ///
+    /// A cursor that is ahead of the queue, e.g. one kept across a deserialized allocator whose
+    /// events were not saved, is also reported as `Lagged` since it cannot tell what it missed.
+    pub fn read(&self, cursor: &mut EventCursor) -> Result<&[E], Lagged> {
+        if cursor.next > self.end() {
+            cursor.next = self.start;
+            return Err(Lagged { missed: 0 });
+        }
+
+        if cursor.next < self.start {
+            let missed = self.start - cursor.next;
+            cursor.next = self.start;
+            return Err(Lagged { missed });
+        }
+
+        let offset = (cursor.next - self.start) as usize;
+        cursor.next = self.end();
+        Ok(&self.events[offset..])
+    }
+
+    /// A cursor that skips every event recorded so far.
+    pub fn cursor(&self) -> EventCursor {
+        EventCursor { next: self.end() }
+    }
+
+    /// Drops every retained event.
+    pub fn clear(&mut self) {
+        self.start = self.end();
+        self.events.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn readers_keep_their_own_position() {
+        let mut queue = EventQueue::default();
+        let mut a = EventCursor::default();
+
+        queue.push(1);
+        queue.push(2);
+        let mut b = queue.cursor();
+        queue.push(3);
+
+        assert_eq!(Ok(&[1, 2, 3][..]), queue.read(&mut a));
+        assert_eq!(Ok(&[3][..]), queue.read(&mut b));
+        assert_eq!(Ok(&[][..]), queue.read(&mut a));
+    }
+
+    #[test]
+    fn clear_lags_behind_readers() {
+        let mut queue = EventQueue::default();
+        let mut behind = EventCursor::default();
+        let mut current = EventCursor::default();
+
+        queue.push(1);
+        queue.push(2);
+        queue.read(&mut current).unwrap();
+        queue.clear();
+        queue.push(3);
+
+        assert_eq!(Err(Lagged { missed: 2 }), queue.read(&mut behind));
+        assert_eq!(Ok(&[3][..]), queue.read(&mut behind));
+        assert_eq!(Ok(&[3][..]), queue.read(&mut current));
+    }
+}
@@ -1,37 +1,159 @@
 use std::marker::PhantomData;
 use std::cmp::Ordering;
+use std::fmt;
+use crate::allocators::{Allocator, Reserved};
+#[cfg(feature = "branded")]
+use crate::allocators::Brand;
+#[cfg(all(feature = "branded", debug_assertions))]
+use crate::allocators::OwnBrand;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 pub trait Indexes<ID>: Copy {
     fn index(&self) -> usize;
+
+    #[cfg(feature = "branded")]
+    fn brand(&self) -> Brand {
+        Brand::NONE
+    }
 }
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(bound = ""))]
 pub struct FixedAllocator<T> {
     next_index: usize,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    reserved: Reserved,
+    #[cfg(all(feature = "branded", debug_assertions))]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    brand: OwnBrand,
+    #[cfg_attr(feature = "serde", serde(skip))]
     marker: PhantomData<T>,
 }
 
+impl<T> Default for FixedAllocator<T> {
+    fn default() -> Self {
+        Self {
+            next_index: 0,
+            reserved: Default::default(),
+            #[cfg(all(feature = "branded", debug_assertions))]
+            brand: Default::default(),
+            marker: PhantomData,
+        }
+    }
+}
+
 impl<T> FixedAllocator<T> {
     pub fn create(&mut self) -> Id<T> {
-        let id = Id::new(self.next_index);
+        self.flush();
+        let id = self.tag(Id::new(self.next_index));
         self.next_index += 1;
         id
     }
+
+    /// Creates `n` entities with consecutive ids.
+    pub fn create_many(&mut self, n: usize) -> IdRange<T> {
+        self.flush();
+        let start = self.tag(Id::new(self.next_index));
+        self.next_index += n;
+        IdRange::new(start, self.next_index)
+    }
+
+    /// Creates the reserved entities in the order they were reserved.
+    pub fn flush(&mut self) {
+        self.next_index += self.reserved.take();
+    }
+
+    #[cfg(feature = "branded")]
+    pub fn brand(&self) -> Brand {
+        #[cfg(debug_assertions)]
+        return self.brand.0;
+        #[cfg(not(debug_assertions))]
+        Brand::NONE
+    }
+
+    fn tag(&self, id: Id<T>) -> Id<T> {
+        #[cfg(all(feature = "branded", debug_assertions))]
+        let id = id.with_brand(self.brand.0);
+        id
+    }
 }
 
-#[derive(Debug)]
+impl<T> Allocator<T> for FixedAllocator<T> {
+    type Id = Id<T>;
+
+    fn create(&mut self) -> Id<T> {
+        FixedAllocator::create(self)
+    }
+
+    fn row(&self, id: &Id<T>) -> Option<Id<T>> {
+        Some(*id)
+    }
+
+    fn is_alive_index(&self, index: usize) -> bool {
+        index < self.next_index
+    }
+
+    fn live_id(&self, index: usize) -> Option<Id<T>> {
+        if self.is_alive_index(index) {
+            Some(self.tag(Id::new(index)))
+        } else {
+            None
+        }
+    }
+
+    fn rows(&self) -> usize {
+        self.next_index
+    }
+
+    fn peek(&self, n: usize) -> Id<T> {
+        self.tag(Id::new(self.next_index + self.reserved.get() + n))
+    }
+
+    fn reserve(&self) -> Id<T> {
+        self.tag(Id::new(self.next_index + self.reserved.next()))
+    }
+
+    fn flush(&mut self) {
+        FixedAllocator::flush(self)
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent, bound = ""))]
 pub struct Id<T> {
     pub(crate) index: usize,
+    #[cfg(all(feature = "branded", debug_assertions))]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) brand: Brand,
+    #[cfg_attr(feature = "serde", serde(skip))]
     marker: PhantomData<T>,
 }
 
+impl<T> fmt::Debug for Id<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut f = f.debug_struct("Id");
+        f.field("index", &self.index);
+        #[cfg(all(feature = "branded", debug_assertions))]
+        f.field("brand", &self.brand);
+        f.finish()
+    }
+}
+
 impl<T> Id<T> {
     pub(crate) fn new(index: usize) -> Self {
         Self {
             index,
+            #[cfg(all(feature = "branded", debug_assertions))]
+            brand: Brand::NONE,
             marker: PhantomData,
         }
     }
+
+    #[cfg(all(feature = "branded", debug_assertions))]
+    pub(crate) fn with_brand(mut self, brand: Brand) -> Self {
+        self.brand = brand;
+        self
+    }
 }
 
 impl<T> PartialEq for Id<T> {
@@ -62,7 +184,7 @@ impl<T> Ord for Id<T> {
 
 impl<T> Clone for Id<T> {
     fn clone(&self) -> Self {
-        Self::new(self.index)
+        *self
     }
 }
 
@@ -72,12 +194,100 @@ impl<ID> Indexes<ID> for Id<ID> {
     fn index(&self) -> usize {
         self.index
     }
+
+    #[cfg(all(feature = "branded", debug_assertions))]
+    fn brand(&self) -> Brand {
+        self.brand
+    }
 }
 
 impl<'a, ID> Indexes<ID> for &'a Id<ID> {
     fn index(&self) -> usize {
         self.index
     }
+
+    #[cfg(all(feature = "branded", debug_assertions))]
+    fn brand(&self) -> Brand {
+        self.brand
+    }
+}
+
+/// Consecutive ids, as returned by `FixedAllocator::create_many`.
+#[derive(Debug)]
+pub struct IdRange<T> {
+    start: Id<T>,
+    end: usize,
+}
+
+impl<T> IdRange<T> {
+    fn new(start: Id<T>, end: usize) -> Self {
+        Self {
+            start,
+            end,
+        }
+    }
+
+    fn at(&self, index: usize) -> Id<T> {
+        let mut id = self.start;
+        id.index = index;
+        id
+    }
+
+    pub fn start(&self) -> Id<T> {
+        self.start
+    }
+
+    pub fn end(&self) -> Id<T> {
+        self.at(self.end)
+    }
+
+    pub fn contains(&self, id: Id<T>) -> bool {
+        (self.start.index..self.end).contains(&id.index)
+    }
+}
+
+impl<T> Clone for IdRange<T> {
+    fn clone(&self) -> Self {
+        Self::new(self.start, self.end)
+    }
+}
+
+impl<T> Iterator for IdRange<T> {
+    type Item = Id<T>;
+
+    fn next(&mut self) -> Option<Id<T>> {
+        if self.start.index < self.end {
+            let id = self.start;
+            self.start.index += 1;
+            Some(id)
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.end - self.start.index;
+        (len, Some(len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IdRange<T> {
+    fn next_back(&mut self) -> Option<Id<T>> {
+        if self.start.index < self.end {
+            self.end -= 1;
+            Some(self.at(self.end))
+        } else {
+            None
+        }
+    }
+}
+
+impl<T> ExactSizeIterator for IdRange<T> {}
+
+impl<T> From<IdRange<T>> for std::ops::Range<Id<T>> {
+    fn from(range: IdRange<T>) -> Self {
+        range.start()..range.end()
+    }
 }
 
 
@@ -95,4 +305,20 @@ mod tests {
         assert_eq!(0, id0.index);
         assert_eq!(1, id1.index);
     }
+
+    #[test]
+    fn create_many() {
+        let mut a = FixedAllocator::<()>::default();
+
+        a.create();
+        let ids = a.create_many(3);
+        let next = a.create();
+
+        assert_eq!(vec![1, 2, 3], ids.clone().map(|id| id.index).collect::<Vec<_>>());
+        assert_eq!(3, ids.len());
+        assert!(ids.contains(Id::new(3)));
+        assert!(!ids.contains(next));
+        assert_eq!(Id::new(1)..Id::new(4), ids.into());
+        assert_eq!(4, next.index);
+    }
 }
@@ -0,0 +1,129 @@
+use std::convert::TryFrom;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::num::{NonZeroU16, NonZeroU32, NonZeroU64};
+#[cfg(feature = "serde")]
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+pub trait GenValue: Copy + Eq + Ord + Hash + Debug + Send + Sync {}
+
+impl<T: Copy + Eq + Ord + Hash + Debug + Send + Sync> GenValue for T {}
+
+/// Integer widths that can count entity generations.
+pub trait Generation: Copy + Eq + Ord + Hash + Debug + Default + 'static {
+    type Value: GenValue;
+
+    fn first() -> Self::Value;
+    fn next(value: Self::Value) -> Option<Self::Value>;
+    fn get(value: Self::Value) -> u64;
+    /// The value with the given count, or `None` if it is zero or too wide.
+    fn from_u64(value: u64) -> Option<Self::Value>;
+}
+
+macro_rules! generation {
+    ($t:ty, $nz:ty) => {
+        impl Generation for $t {
+            type Value = $nz;
+
+            fn first() -> $nz {
+                <$nz>::MIN
+            }
+
+            fn next(value: $nz) -> Option<$nz> {
+                value.checked_add(1)
+            }
+
+            fn get(value: $nz) -> u64 {
+                value.get() as u64
+            }
+
+            fn from_u64(value: u64) -> Option<$nz> {
+                <$t>::try_from(value).ok().and_then(<$nz>::new)
+            }
+        }
+    };
+}
+
+generation!(u16, NonZeroU16);
+generation!(u32, NonZeroU32);
+generation!(u64, NonZeroU64);
+
+/// Serialized as its count, so any `Generation` can be saved without extra bounds.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Gen<G: Generation = u32>(G::Value);
+
+#[cfg(feature = "serde")]
+impl<G: Generation> Serialize for Gen<G> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(self.get())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, G: Generation> Deserialize<'de> for Gen<G> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = u64::deserialize(deserializer)?;
+        G::from_u64(value)
+            .map(Gen)
+            .ok_or_else(|| D::Error::custom(format!("generation {} is out of range", value)))
+    }
+}
+
+impl<G: Generation> Default for Gen<G> {
+    fn default() -> Self {
+        Gen(G::first())
+    }
+}
+
+impl<G: Generation> Gen<G> {
+    /// The following generation, or `None` once the generation type is exhausted.
+    pub fn next(self) -> Option<Self> {
+        G::next(self.0).map(Gen)
+    }
+
+    pub fn get(self) -> u64 {
+        G::get(self.0)
+    }
+}
+
+/// What a `DynamicAllocator` does with a slot whose generation can no longer be incremented.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum GenOverflow {
+    /// Never hand the slot out again.
+    #[default]
+    Retire,
+    /// Start the slot over at the first generation, which can revive very old ids.
+    Wrap,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn next_until_exhausted() {
+        let gen = Gen::<u16>::default();
+        assert_eq!(1, gen.get());
+        assert_eq!(Some(2), gen.next().map(Gen::get));
+
+        let last = Gen::<u16>(NonZeroU16::MAX);
+        assert_eq!(None, last.next());
+    }
+
+    #[test]
+    fn from_u64_rejects_out_of_range_counts() {
+        assert_eq!(NonZeroU16::new(7), u16::from_u64(7));
+        assert_eq!(None, u16::from_u64(0));
+        assert_eq!(None, u16::from_u64(1 << 16));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serializes_as_count() {
+        let json = serde_json::to_string(&Gen::<u64>::default()).unwrap();
+        assert_eq!("1", json);
+        assert_eq!(Gen::<u64>::default(), serde_json::from_str(&json).unwrap());
+        assert!(serde_json::from_str::<Gen<u16>>("70000").is_err());
+    }
+}
@@ -1,5 +1,36 @@
+#[cfg(feature = "branded")]
+pub use brand::*;
+pub use dense_map::*;
 pub use dynamic_allocator::*;
+pub use events::*;
 pub use fixed_allocator::*;
+pub use gen::*;
+pub(crate) use reserved::Reserved;
 
+#[cfg(feature = "branded")]
+mod brand;
+mod dense_map;
 mod dynamic_allocator;
-mod fixed_allocator;
\ No newline at end of file
+mod events;
+mod fixed_allocator;
+mod gen;
+mod reserved;
+
+pub trait Allocator<T> {
+    type Id: Indexes<T>;
+
+    fn create(&mut self) -> Self::Id;
+    /// The column row holding the components of `id`, if it is still alive.
+    fn row(&self, id: &Self::Id) -> Option<Id<T>>;
+    fn is_alive_index(&self, index: usize) -> bool;
+    fn live_id(&self, index: usize) -> Option<Self::Id>;
+    /// The number of rows each column indexed by this allocator should hold.
+    fn rows(&self) -> usize;
+    /// The id that the `n`th next call to `create` will return, if nothing is killed in between.
+    fn peek(&self, n: usize) -> Self::Id;
+    /// Reserves the id of an entity that is created by the next `flush`. Reserved ids are never
+    /// handed out again: `create` and `kill` flush the reservations before changing anything.
+    fn reserve(&self) -> Self::Id;
+    /// Creates the reserved entities in the order they were reserved.
+    fn flush(&mut self);
+}
@@ -0,0 +1,34 @@
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// The number of ids reserved through a shared reference and not yet created.
+#[derive(Default)]
+pub(crate) struct Reserved(AtomicUsize);
+
+impl Reserved {
+    /// Claims the next reservation, returning how many were claimed before it.
+    pub(crate) fn next(&self) -> usize {
+        self.0.fetch_add(1, Ordering::Relaxed)
+    }
+
+    pub(crate) fn get(&self) -> usize {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Clears the reservations, returning how many there were.
+    pub(crate) fn take(&mut self) -> usize {
+        std::mem::take(self.0.get_mut())
+    }
+}
+
+impl Clone for Reserved {
+    fn clone(&self) -> Self {
+        Reserved(AtomicUsize::new(self.get()))
+    }
+}
+
+impl fmt::Debug for Reserved {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.get().fmt(f)
+    }
+}
@@ -0,0 +1,88 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+const BITS: usize = 64;
+
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
+pub struct BitSet {
+    blocks: Vec<u64>,
+}
+
+impl BitSet {
+    pub fn get(&self, index: usize) -> bool {
+        self.blocks
+            .get(index / BITS)
+            .map(|block| block & Self::mask(index) != 0)
+            .unwrap_or(false)
+    }
+
+    pub fn set(&mut self, index: usize, value: bool) {
+        if value {
+            self.insert(index);
+        } else {
+            self.remove(index);
+        }
+    }
+
+    pub fn insert(&mut self, index: usize) {
+        let block = index / BITS;
+        if block >= self.blocks.len() {
+            self.blocks.resize(block + 1, 0);
+        }
+        self.blocks[block] |= Self::mask(index);
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        if let Some(block) = self.blocks.get_mut(index / BITS) {
+            *block &= !Self::mask(index);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.blocks.clear();
+    }
+
+    pub fn count(&self) -> usize {
+        self.blocks.iter().map(|block| block.count_ones() as usize).sum()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.blocks
+            .iter()
+            .enumerate()
+            .flat_map(|(i, block)| {
+                let block = *block;
+                (0..BITS)
+                    .filter(move |bit| block & (1 << bit) != 0)
+                    .map(move |bit| i * BITS + bit)
+            })
+    }
+
+    fn mask(index: usize) -> u64 {
+        1 << (index % BITS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_and_remove() {
+        let mut bits = BitSet::default();
+
+        bits.insert(3);
+        bits.insert(64);
+        bits.insert(130);
+        assert!(bits.get(3));
+        assert!(bits.get(64));
+        assert!(!bits.get(4));
+        assert!(!bits.get(1000));
+
+        bits.remove(64);
+        assert!(!bits.get(64));
+        assert_eq!(vec![3, 130], bits.iter().collect::<Vec<_>>());
+        assert_eq!(2, bits.count());
+    }
+}
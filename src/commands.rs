@@ -0,0 +1,202 @@
+use crate::allocators::{Allocator, DenseRow, DynamicAllocator, GenId, Generation, Id};
+use crate::world::{Entity, EntityId, World};
+use crate::{Insert, Remove, Table};
+use std::any::Any;
+use std::fmt;
+
+type Command = Box<dyn FnOnce(&World) + Send>;
+
+/// Records structural changes while columns are borrowed, to be applied later with `apply`.
+///
+/// Commands are applied in a fixed order: creates, then inserts, then links, then kills, each in
+/// the order they were recorded. Ids of created entities are reserved in the allocator up front,
+/// so they can be used by other commands in the same buffer and are never handed out twice, even
+/// by other buffers or by `World::create`. See `World::reserve` for when reserved entities are
+/// created.
+#[derive(Default)]
+pub struct Commands {
+    creates: Vec<Command>,
+    inserts: Vec<Command>,
+    links: Vec<Command>,
+    kills: Vec<Command>,
+}
+
+impl Commands {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reserves the id of a new entity whose row is inserted when the commands are applied.
+    pub fn create<E>(&mut self, world: &World, row: E::Row) -> EntityId<E>
+    where
+        E: Entity + Table,
+        E::Row: Send + 'static,
+        EntityId<E>: Send + 'static,
+    {
+        self.creates.push(Box::new(|world| world.flush::<E>()));
+        world.reserve::<E>(row)
+    }
+
+    /// Writes to the entity's row of its table, if the entity is still alive.
+    pub fn insert<E>(&mut self, id: EntityId<E>, f: impl FnOnce(&mut E, Id<E>) + Send + 'static)
+    where
+        E: Entity,
+        EntityId<E>: Send + 'static,
+    {
+        self.inserts.push(Box::new(move |world| {
+            if let Some(row) = world.alloc::<E>().row(&id) {
+                f(&mut world.table_mut::<E>(), row);
+            }
+        }));
+    }
+
+    /// Inserts into a link table stored in the world, such as `GenIds` or `Children`.
+    pub fn link<L, I, V>(&mut self, id: I, value: V)
+    where
+        L: Insert<I, V> + Any + Send + Sync,
+        I: Send + 'static,
+        V: Send + 'static,
+    {
+        self.links.push(Box::new(move |world| world.get_mut::<L>().insert(id, value)));
+    }
+
+    /// Kills the entity and removes its row from the table.
+    pub fn kill<E, G>(&mut self, id: GenId<E, G>)
+    where
+        E: Entity<Allocator = DynamicAllocator<E, G>> + Remove<DenseRow<E>>,
+        G: Generation,
+    {
+        self.kills.push(Box::new(move |world| {
+            world.alloc_mut::<E>().kill_and_remove(id, &mut *world.table_mut::<E>());
+        }));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.creates.is_empty() && self.inserts.is_empty() && self.links.is_empty() && self.kills.is_empty()
+    }
+
+    /// Applies and clears the recorded commands.
+    pub fn apply(&mut self, world: &World) {
+        let commands = self.creates.drain(..)
+            .chain(self.inserts.drain(..))
+            .chain(self.links.drain(..))
+            .chain(self.kills.drain(..));
+
+        for command in commands {
+            command(world);
+        }
+    }
+}
+
+impl fmt::Debug for Commands {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Commands")
+            .field("creates", &self.creates.len())
+            .field("inserts", &self.inserts.len())
+            .field("links", &self.links.len())
+            .field("kills", &self.kills.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::allocators::Indexes;
+    use crate::components::Comp1;
+    use crate::links::GenIds;
+    use crate::Get1;
+
+    #[derive(Debug, Default)]
+    struct Colony {
+        population: Comp1<Self, u32>,
+    }
+
+    impl Entity for Colony {
+        type Allocator = DynamicAllocator<Self>;
+    }
+
+    impl Table for Colony {
+        type Row = u32;
+
+        fn insert_row<I: Indexes<Self>>(&mut self, id: I, row: u32) {
+            self.population.insert(id, row);
+        }
+
+        fn column_lengths(&self) -> Vec<(&'static str, usize)> {
+            vec![("population", self.population.len())]
+        }
+    }
+
+    impl Remove<DenseRow<Colony>> for Colony {
+        fn remove(&mut self, removed: DenseRow<Colony>) {
+            self.population.remove(removed);
+        }
+    }
+
+    type Founders = GenIds<Colony, Colony>;
+
+    fn world() -> World {
+        let mut world = World::new();
+        world.register::<Colony>();
+        world.insert(Founders::default());
+        world
+    }
+
+    #[test]
+    fn reserved_ids_match_created_ids() {
+        let world = world();
+        let mut commands = Commands::new();
+
+        let old = world.create::<Colony>(10);
+        let doomed = world.create::<Colony>(20);
+        world.get_mut::<Founders>().insert(old.index, None);
+        world.get_mut::<Founders>().insert(doomed.index, None);
+        world.alloc_mut::<Colony>().kill(doomed);
+
+        let first = commands.create::<Colony>(&world, 5);
+        let second = commands.create::<Colony>(&world, 1);
+        commands.link::<Founders, _, _>(first.index, Some(old));
+        commands.link::<Founders, _, _>(second.index, Some(first));
+        commands.insert::<Colony>(old, |colony, row| *colony.population.get_mut(row).unwrap() -= 5);
+        commands.kill(old);
+        assert_eq!(doomed.index, first.index);
+
+        commands.apply(&world);
+        assert!(commands.is_empty());
+
+        let alloc = world.alloc::<Colony>();
+        assert!(!alloc.is_valid(&old));
+        assert!(alloc.is_valid(&first) && alloc.is_valid(&second));
+        assert_eq!(Some(&1), world.table::<Colony>().population.get(alloc.row(&second).unwrap()));
+
+        let founders = world.get::<Founders>();
+        assert_eq!(Some(&Some(first.index)), founders.ids.0.get(second.index));
+    }
+
+    #[test]
+    fn buffers_reserve_distinct_ids() {
+        let world = world();
+        let mut first = Commands::new();
+        let mut second = Commands::new();
+
+        let doomed = world.create::<Colony>(0);
+        world.alloc_mut::<Colony>().kill(doomed);
+
+        let a = first.create::<Colony>(&world, 1);
+        let b = second.create::<Colony>(&world, 2);
+        let c = first.create::<Colony>(&world, 3);
+        let d = world.create::<Colony>(4);
+        assert_eq!(doomed.index, a.index);
+
+        second.apply(&world);
+        first.apply(&world);
+
+        let alloc = world.alloc::<Colony>();
+        let table = world.table::<Colony>();
+        for (id, population) in [(a, 1), (b, 2), (c, 3), (d, 4)] {
+            assert_eq!(Some(&population), table.population.get(alloc.row(&id).unwrap()));
+        }
+        assert_eq!(Ok(()), table.check_lengths(&*alloc));
+    }
+}
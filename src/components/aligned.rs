@@ -0,0 +1,347 @@
+use std::alloc::{self, Layout};
+use std::fmt;
+use std::mem;
+use std::ops::{Deref, DerefMut};
+use std::ptr::{self, NonNull};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Alignment of the allocation, one 256-bit register.
+pub const ALIGN: usize = 32;
+
+/// A growable array like `Vec`, whose buffer starts on an `ALIGN` boundary, so that a column split
+/// into `LANES`-wide chunks of 32-bit values has every chunk on a register boundary.
+///
+/// Derefs to a slice; the `Vec` methods the columns need are implemented directly.
+pub struct AlignedVec<T> {
+    ptr: NonNull<T>,
+    len: usize,
+    cap: usize,
+}
+
+unsafe impl<T: Send> Send for AlignedVec<T> {}
+unsafe impl<T: Sync> Sync for AlignedVec<T> {}
+
+impl<T> AlignedVec<T> {
+    pub fn new() -> Self {
+        Self {
+            ptr: Self::dangling(),
+            len: 0,
+            cap: if mem::size_of::<T>() == 0 { usize::MAX } else { 0 },
+        }
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut vec = Self::new();
+        vec.reserve(capacity);
+        vec
+    }
+
+    fn dangling() -> NonNull<T> {
+        let align = Self::align();
+        // An address equal to the alignment is non-null and aligned, and never dereferenced.
+        unsafe { NonNull::new_unchecked(align as *mut T) }
+    }
+
+    fn align() -> usize {
+        mem::align_of::<T>().max(ALIGN)
+    }
+
+    fn layout(cap: usize) -> Layout {
+        let size = mem::size_of::<T>().checked_mul(cap).expect("capacity overflow");
+        Layout::from_size_align(size, Self::align()).expect("capacity overflow")
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.cap
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        unsafe { std::slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        unsafe { std::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+
+    pub fn as_ptr(&self) -> *const T {
+        self.ptr.as_ptr()
+    }
+
+    pub fn as_mut_ptr(&mut self) -> *mut T {
+        self.ptr.as_ptr()
+    }
+
+    /// # Safety
+    ///
+    /// `len` must not exceed the capacity, and the values up to it must be initialized.
+    pub unsafe fn set_len(&mut self, len: usize) {
+        debug_assert!(len <= self.cap);
+        self.len = len;
+    }
+
+    /// Reserves room for at least `additional` more values.
+    pub fn reserve(&mut self, additional: usize) {
+        let required = self.len.checked_add(additional).expect("capacity overflow");
+        if required <= self.cap {
+            return;
+        }
+
+        let cap = required.max(self.cap * 2).max(4);
+        let layout = Self::layout(cap);
+        let ptr = unsafe {
+            if self.cap == 0 {
+                alloc::alloc(layout)
+            } else {
+                alloc::realloc(self.ptr.as_ptr() as *mut u8, Self::layout(self.cap), layout.size())
+            }
+        };
+
+        self.ptr = match NonNull::new(ptr as *mut T) {
+            Some(ptr) => ptr,
+            None => alloc::handle_alloc_error(layout),
+        };
+        self.cap = cap;
+    }
+
+    pub fn push(&mut self, value: T) {
+        if self.len == self.cap {
+            self.reserve(1);
+        }
+        unsafe { ptr::write(self.ptr.as_ptr().add(self.len), value) };
+        self.len += 1;
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        Some(unsafe { ptr::read(self.ptr.as_ptr().add(self.len)) })
+    }
+
+    /// Removes the value at `index`, moving the last value into its place. Panics if `index` is out of bounds.
+    pub fn swap_remove(&mut self, index: usize) -> T {
+        let len = self.len;
+        assert!(index < len, "swap_remove index {} is out of bounds for length {}", index, len);
+        self.as_mut_slice().swap(index, len - 1);
+        self.pop().unwrap()
+    }
+
+    pub fn truncate(&mut self, len: usize) {
+        while self.len > len {
+            self.pop();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.truncate(0);
+    }
+
+    pub fn resize_with(&mut self, len: usize, mut fill: impl FnMut() -> T) {
+        if len <= self.len {
+            self.truncate(len);
+            return;
+        }
+        self.reserve(len - self.len);
+        while self.len < len {
+            self.push(fill());
+        }
+    }
+
+    pub fn into_vec(self) -> Vec<T> {
+        let mut vec = Vec::with_capacity(self.len);
+        let mut this = mem::ManuallyDrop::new(self);
+        unsafe {
+            ptr::copy_nonoverlapping(this.ptr.as_ptr(), vec.as_mut_ptr(), this.len);
+            vec.set_len(this.len);
+            this.len = 0;
+            ptr::drop_in_place(&mut *this);
+        }
+        vec
+    }
+}
+
+impl<T: Clone> AlignedVec<T> {
+    pub fn resize(&mut self, len: usize, value: T) {
+        self.resize_with(len, || value.clone());
+    }
+
+    pub fn extend_from_slice(&mut self, values: &[T]) {
+        self.extend(values.iter().cloned());
+    }
+}
+
+impl<T> Drop for AlignedVec<T> {
+    fn drop(&mut self) {
+        unsafe { ptr::drop_in_place(self.as_mut_slice()) };
+        if self.cap != 0 && mem::size_of::<T>() != 0 {
+            unsafe { alloc::dealloc(self.ptr.as_ptr() as *mut u8, Self::layout(self.cap)) };
+        }
+    }
+}
+
+impl<T> Default for AlignedVec<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Deref for AlignedVec<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<T> DerefMut for AlignedVec<T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        self.as_mut_slice()
+    }
+}
+
+impl<T: Clone> Clone for AlignedVec<T> {
+    fn clone(&self) -> Self {
+        self.iter().cloned().collect()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for AlignedVec<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_slice().fmt(f)
+    }
+}
+
+impl<T: PartialEq> PartialEq for AlignedVec<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl<T: Eq> Eq for AlignedVec<T> {}
+
+impl<T: PartialEq> PartialEq<Vec<T>> for AlignedVec<T> {
+    fn eq(&self, other: &Vec<T>) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl<T: PartialEq> PartialEq<AlignedVec<T>> for Vec<T> {
+    fn eq(&self, other: &AlignedVec<T>) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl<T> Extend<T> for AlignedVec<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        iter.for_each(|value| self.push(value));
+    }
+}
+
+impl<T> std::iter::FromIterator<T> for AlignedVec<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut vec = Self::new();
+        vec.extend(iter);
+        vec
+    }
+}
+
+impl<T> From<Vec<T>> for AlignedVec<T> {
+    fn from(vec: Vec<T>) -> Self {
+        vec.into_iter().collect()
+    }
+}
+
+impl<T> IntoIterator for AlignedVec<T> {
+    type Item = T;
+    type IntoIter = std::vec::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.into_vec().into_iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a AlignedVec<T> {
+    type Item = &'a T;
+    type IntoIter = std::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut AlignedVec<T> {
+    type Item = &'a mut T;
+    type IntoIter = std::slice::IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T: Serialize> Serialize for AlignedVec<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.as_slice().serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for AlignedVec<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::deserialize(deserializer).map(Self::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buffer_stays_aligned_as_it_grows() {
+        let mut values = AlignedVec::<u8>::new();
+        for i in 0..100 {
+            values.push(i);
+            assert_eq!(0, values.as_ptr() as usize % ALIGN);
+        }
+        assert_eq!((0..100).collect::<Vec<u8>>(), values);
+    }
+
+    #[test]
+    fn drops_each_value_once() {
+        let value = std::rc::Rc::new(());
+        let mut values = AlignedVec::new();
+        values.resize(10, value.clone());
+        assert_eq!(11, std::rc::Rc::strong_count(&value));
+
+        values.swap_remove(3);
+        values.truncate(5);
+        assert_eq!(6, std::rc::Rc::strong_count(&value));
+
+        let vec = values.clone().into_vec();
+        assert_eq!(11, std::rc::Rc::strong_count(&value));
+
+        drop(values);
+        drop(vec);
+        assert_eq!(1, std::rc::Rc::strong_count(&value));
+    }
+
+    #[test]
+    fn zero_sized_values() {
+        let mut values = AlignedVec::new();
+        values.resize(3, ());
+        assert_eq!(Some(()), values.pop());
+        assert_eq!(2, values.len());
+    }
+}
@@ -1,16 +1,59 @@
 use super::*;
-use crate::allocators::{Indexes, Valid};
+use crate::allocators::{Allocator, Indexes, Valid};
+#[cfg(all(feature = "branded", debug_assertions))]
+use crate::allocators::ColumnBrand;
+use std::fmt;
+use std::slice::{ChunksExact, ChunksExactMut};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent, bound(serialize = "T: Serialize", deserialize = "T: Deserialize<'de>")))]
 pub struct Comp1<ID, T> {
-    pub values: Vec<T>,
+    pub values: AlignedVec<T>,
+    #[cfg(all(feature = "branded", debug_assertions))]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    brand: ColumnBrand,
+    #[cfg_attr(feature = "serde", serde(skip))]
     marker: PhantomData<ID>,
 }
 
+/// Fixed-width view of a column, with the values that do not fill a whole chunk in `remainder`.
+/// The column is allocated on an `ALIGN` boundary, so chunks whose size in bytes divides `ALIGN`
+/// each start on one.
+#[derive(Debug, Clone)]
+pub struct Chunks<'a, T> {
+    pub body: ChunksExact<'a, T>,
+    pub remainder: &'a [T],
+}
+
+#[derive(Debug)]
+pub struct ChunksMut<'a, T> {
+    pub body: ChunksExactMut<'a, T>,
+    pub remainder: &'a mut [T],
+}
+
+/// Returned by `Comp1::try_insert` when there are missing rows between the end of the column and the index.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct IndexGap {
+    pub index: usize,
+    pub len: usize,
+}
+
+impl fmt::Display for IndexGap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "index {} is past the end of a column of length {}", self.index, self.len)
+    }
+}
+
+impl std::error::Error for IndexGap {}
+
 impl<ID, T> Default for Comp1<ID, T> {
     fn default() -> Self {
         Self {
             values: Default::default(),
+            #[cfg(all(feature = "branded", debug_assertions))]
+            brand: Default::default(),
             marker: PhantomData,
         }
     }
@@ -18,27 +61,39 @@ impl<ID, T> Default for Comp1<ID, T> {
 
 impl<ID, T, I: Indexes<ID>> Insert<I, T> for Comp1<ID, T> {
     fn insert(&mut self, id: I, value: T) {
+        self.bind(id);
         self.insert_index(id.index(), value);
     }
 }
 
+impl<ID, T> Remove<DenseRow<ID>> for Comp1<ID, T> {
+    fn remove(&mut self, removed: DenseRow<ID>) {
+        self.check(removed.row());
+        if removed.row().index < self.len() {
+            self.values.swap_remove(removed.row().index);
+        }
+    }
+}
+
 impl<ID, T> Get1<Id<ID>, T> for Comp1<ID, T> {
     fn get(&self, id: Id<ID>) -> Option<&T> {
+        self.check(id);
         self.get_index(id.index)
     }
 
     fn get_mut(&mut self, id: Id<ID>) -> Option<&mut T> {
+        self.check(id);
         self.get_mut_index(id.index)
     }
 }
 
 impl<ID, T> Get1<&Id<ID>, T> for Comp1<ID, T> {
     fn get(&self, id: &Id<ID>) -> Option<&T> {
-        self.get_index(id.index)
+        self.get(*id)
     }
 
     fn get_mut(&mut self, id: &Id<ID>) -> Option<&mut T> {
-        self.get_mut_index(id.index)
+        self.get_mut(*id)
     }
 }
 
@@ -63,6 +118,19 @@ impl<ID, T> Get1<&Option<Id<ID>>, T> for Comp1<ID, T> {
 }
 
 impl<ID, T> Comp1<ID, T> {
+    /// Ties the column to the allocator of the first branded id written to it.
+    #[cfg_attr(not(all(feature = "branded", debug_assertions)), allow(unused_variables))]
+    fn bind<I: Indexes<ID>>(&mut self, id: I) {
+        #[cfg(all(feature = "branded", debug_assertions))]
+        self.brand.bind::<ID>(id.brand());
+    }
+
+    #[cfg_attr(not(all(feature = "branded", debug_assertions)), allow(unused_variables))]
+    fn check<I: Indexes<ID>>(&self, id: I) {
+        #[cfg(all(feature = "branded", debug_assertions))]
+        self.brand.check::<ID>(id.brand());
+    }
+
     fn get_index(&self, index: usize) -> Option<&T> {
         self.values.get(index)
     }
@@ -84,10 +152,59 @@ impl<ID, T> Comp1<ID, T> {
         }
     }
 
+    /// Inserts like `Insert::insert`, but returns the gap instead of dropping the value when `id` is past the end.
+    pub fn try_insert<I: Indexes<ID>>(&mut self, id: I, value: T) -> Result<(), IndexGap> {
+        self.bind(id);
+        let index = id.index();
+        match self.len() {
+            len if len < index => Err(IndexGap { index, len }),
+            _ => {
+                self.insert_index(index, value);
+                Ok(())
+            }
+        }
+    }
+
+    /// Inserts at any index, first filling the rows before it with `fill`.
+    pub fn insert_with<I: Indexes<ID>>(&mut self, id: I, value: T, fill: impl FnMut() -> T) {
+        self.bind(id);
+        let index = id.index();
+        if self.len() < index {
+            self.resize_with(index, fill);
+        }
+        self.insert_index(index, value);
+    }
+
+    /// Inserts at any index, first filling the rows before it with `T::default()`.
+    pub fn insert_with_default<I: Indexes<ID>>(&mut self, id: I, value: T)
+    where
+        T: Default,
+    {
+        self.insert_with(id, value, T::default);
+    }
+
+    /// Inserts a value for each id, reserving room for all of them first.
+    pub fn extend_from<I: Indexes<ID>>(&mut self, ids: impl IntoIterator<Item = I>, values: impl IntoIterator<Item = T>) {
+        let values = values.into_iter();
+        self.values.reserve(values.size_hint().0);
+        ids.into_iter()
+            .zip(values)
+            .for_each(|(id, value)| self.insert(id, value));
+    }
+
+    /// Grows or truncates the column to `len` rows, creating new values with `fill`.
+    pub fn resize_with(&mut self, len: usize, fill: impl FnMut() -> T) {
+        self.values.resize_with(len, fill);
+    }
+
     pub fn len(&self) -> usize {
         self.values.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
     pub fn iter(&self) -> impl Iterator<Item = &T> {
         self.values.iter()
     }
@@ -95,6 +212,43 @@ impl<ID, T> Comp1<ID, T> {
     pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
         self.values.iter_mut()
     }
+
+    /// Panics if `size` is zero.
+    pub fn chunks(&self, size: usize) -> Chunks<'_, T> {
+        let body = self.values.chunks_exact(size);
+        let remainder = body.remainder();
+        Chunks { body, remainder }
+    }
+
+    /// Panics if `size` is zero.
+    pub fn chunks_mut(&mut self, size: usize) -> ChunksMut<'_, T> {
+        let split = self.len() - self.len() % size;
+        let (body, remainder) = self.values.split_at_mut(split);
+        ChunksMut { body: body.chunks_exact_mut(size), remainder }
+    }
+
+    pub fn iter_alive<'a, A: Allocator<ID>>(&'a self, alloc: &'a A) -> impl Iterator<Item = &'a T> + 'a {
+        self.values
+            .iter()
+            .enumerate()
+            .filter(move |(i, _)| alloc.is_alive_index(*i))
+            .map(|(_, v)| v)
+    }
+
+    pub fn iter_mut_alive<'a, A: Allocator<ID>>(&'a mut self, alloc: &'a A) -> impl Iterator<Item = &'a mut T> + 'a {
+        self.values
+            .iter_mut()
+            .enumerate()
+            .filter(move |(i, _)| alloc.is_alive_index(*i))
+            .map(|(_, v)| v)
+    }
+
+    pub fn iter_with_ids<'a, A: Allocator<ID>>(&'a self, alloc: &'a A) -> impl Iterator<Item = (A::Id, &'a T)> + 'a {
+        self.values
+            .iter()
+            .enumerate()
+            .filter_map(move |(i, v)| alloc.live_id(i).map(|id| (id, v)))
+    }
 }
 
 impl<ID1, T: Copy> Comp1<ID1, T> {
@@ -114,7 +268,7 @@ impl<ID1, T: Copy> Comp1<ID1, T> {
 
     pub fn get_from_or<ID2>(&mut self, rhs: &Comp1<ID2, T>, ids: &Valid<ID1, ID2>, fallback: T) {
         self.iter_mut()
-            .zip(ids.ids.iter())
+            .zip(ids.iter())
             .for_each(|(v, id)|
                 *v = id.and_then(|id| rhs.get(id))
                     .copied()
@@ -126,7 +280,82 @@ impl<ID1, T: Copy> Comp1<ID1, T> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::allocators::FixedAllocator;
+    use crate::allocators::{DynamicAllocator, FixedAllocator};
+
+    #[cfg(all(feature = "branded", debug_assertions))]
+    #[test]
+    #[should_panic(expected = "for `()`")]
+    fn branded_id_from_other_allocator_panics() {
+        let mut world1 = FixedAllocator::<()>::default();
+        let mut world2 = FixedAllocator::<()>::default();
+        let mut values = Comp1::<(), u32>::default();
+
+        values.insert(world1.create(), 1);
+        assert_eq!(Some(&1), values.get(Id::new(0)));
+
+        values.get(world2.create());
+    }
+
+    #[cfg(all(feature = "branded", debug_assertions))]
+    #[test]
+    fn branded_ids_from_same_allocator() {
+        let mut a = DynamicAllocator::<()>::compacting();
+        let mut values = Comp1::<(), u32>::default();
+
+        let id = a.create();
+        values.insert(a.row(&id).unwrap(), 1);
+        let row = a.row(&id).unwrap();
+
+        assert_eq!(a.brand(), row.brand);
+        assert_eq!(Some(&1), values.get(row));
+    }
+
+    #[cfg(all(feature = "branded", debug_assertions))]
+    #[test]
+    #[should_panic(expected = "used with a column of allocator #")]
+    fn cloned_allocator_has_its_own_brand() {
+        let mut a = FixedAllocator::<()>::default();
+        let mut values = Comp1::<(), u32>::default();
+        values.insert(a.create(), 1);
+
+        let mut b = a.clone();
+        assert_ne!(a.brand(), b.brand());
+        values.get(b.create());
+    }
+
+    #[test]
+    fn try_insert_reports_gap() {
+        let mut values = Comp1::<(), u32>::default();
+
+        assert_eq!(Ok(()), values.try_insert(Id::new(0), 1));
+        assert_eq!(Err(IndexGap { index: 3, len: 1 }), values.try_insert(Id::new(3), 4));
+        assert_eq!(Ok(()), values.try_insert(Id::new(0), 2));
+        assert_eq!(vec![2], values.values);
+    }
+
+    #[test]
+    fn extend_from_lines_up_with_ids() {
+        let mut a = FixedAllocator::<()>::default();
+        let mut values = Comp1::<(), u32>::default();
+
+        values.insert(a.create(), 0);
+        let ids = a.create_many(3);
+        values.extend_from(ids.clone(), vec![10, 20, 30]);
+
+        assert_eq!(vec![0, 10, 20, 30], values.values);
+        assert_eq!(vec![&10, &20, &30], ids.map(|id| values.get(id).unwrap()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn insert_out_of_order() {
+        let mut values = Comp1::<(), u32>::default();
+
+        values.insert_with_default(Id::new(2), 3);
+        values.insert_with(Id::new(4), 5, || 9);
+        values.insert_with_default(Id::new(0), 1);
+
+        assert_eq!(vec![1, 0, 3, 9, 5], values.values);
+    }
 
     #[test]
     fn zip_comp1_to_comp1() {
@@ -227,8 +456,8 @@ mod tests {
         assert_eq!(42, t1.values[0]);
     }
 
-    #[derive(Default)] struct Type1;
-    #[derive(Default)] struct Type2;
+    #[derive(Debug, Default)] struct Type1;
+    #[derive(Debug, Default)] struct Type2;
 
     #[test]
     fn get_from_id() {
@@ -258,4 +487,58 @@ mod tests {
 
         assert_eq!(vec![2, 3, 5], to_values.values);
     }
+
+    #[test]
+    fn chunks_with_remainder() {
+        let mut a = FixedAllocator::<()>::default();
+        let mut t = Comp1::<(), u32>::default();
+
+        for i in 0..10 {
+            t.insert(a.create(), i);
+        }
+
+        let chunks = t.chunks(4);
+        assert_eq!(vec![&[0, 1, 2, 3], &[4, 5, 6, 7]], chunks.body.collect::<Vec<_>>());
+        assert_eq!(&[8, 9], chunks.remainder);
+
+        let chunks = t.chunks_mut(4);
+        chunks.body.flatten().for_each(|v| *v = 0);
+        chunks.remainder.iter_mut().for_each(|v| *v = 1);
+        assert_eq!(vec![0, 0, 0, 0, 0, 0, 0, 0, 1, 1], t.values);
+
+        assert!(t.chunks(8).body.all(|chunk| chunk.as_ptr() as usize % ALIGN == 0));
+    }
+
+    #[test]
+    fn iter_alive_skips_dead() {
+        let mut alloc = DynamicAllocator::<Type1>::default();
+        let mut values = Comp1::<Type1, u32>::default();
+
+        let ids = (0..4).map(|i| {
+            let id = alloc.create();
+            values.insert(id, i);
+            id
+        }).collect::<Vec<_>>();
+
+        alloc.kill(ids[1]);
+
+        assert_eq!(vec![&0, &2, &3], values.iter_alive(&alloc).collect::<Vec<_>>());
+
+        values.iter_mut_alive(&alloc).for_each(|v| *v += 10);
+        assert_eq!(vec![10, 1, 12, 13], values.values);
+
+        let with_ids = values.iter_with_ids(&alloc).collect::<Vec<_>>();
+        assert_eq!(vec![(ids[0], &10), (ids[2], &12), (ids[3], &13)], with_ids);
+    }
+
+    #[test]
+    fn iter_alive_fixed() {
+        let mut alloc = FixedAllocator::<Type1>::default();
+        let mut values = Comp1::<Type1, u32>::default();
+
+        let id = alloc.create();
+        values.insert(id, 3);
+
+        assert_eq!(vec![(id, &3)], values.iter_with_ids(&alloc).collect::<Vec<_>>());
+    }
 }
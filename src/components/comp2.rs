@@ -1,7 +1,13 @@
 use super::*;
-use crate::allocators::{Indexes, Gen, Valid};
+use crate::allocators::{Allocator, Indexes, Gen, Generation, Valid};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(bound(
+    serialize = "T1: Serialize, T2: Serialize",
+    deserialize = "T1: Deserialize<'de>, T2: Deserialize<'de>",
+)))]
 pub struct Comp2<ID, T1, T2>(pub Comp1<ID, T1>, pub Comp1<ID, T2>);
 
 impl<ID, T1, T2> Default for Comp2<ID, T1, T2> {
@@ -17,14 +23,21 @@ impl<ID, T1, T2, I: Indexes<ID>> Insert<I, (T1, T2)> for Comp2<ID, T1, T2> {
     }
 }
 
-impl<ID1, ID2, I: Indexes<ID1>> Insert<I, Option<GenId<ID2>>> for Comp2<ID1, Option<Id<ID2>>, Option<Gen>> {
-    fn insert(&mut self, id: I, value: Option<GenId<ID2>>) {
+impl<ID1, ID2, G: Generation, I: Indexes<ID1>> Insert<I, Option<GenId<ID2, G>>> for Comp2<ID1, Option<Id<ID2>>, Option<Gen<G>>> {
+    fn insert(&mut self, id: I, value: Option<GenId<ID2, G>>) {
         let t = value.map(|v| (Some(v.index), Some(v.gen))).unwrap_or((None, None));
         self.0.insert(id, t.0);
         self.1.insert(id, t.1);
     }
 }
 
+impl<ID, T1, T2> Remove<DenseRow<ID>> for Comp2<ID, T1, T2> {
+    fn remove(&mut self, removed: DenseRow<ID>) {
+        self.0.remove(removed);
+        self.1.remove(removed);
+    }
+}
+
 impl<ID, T1, T2> Get2<Id<ID>, T1, T2> for Comp2<ID, T1, T2> {
     fn get(&self, id: Id<ID>) -> Option<(&T1, &T2)> {
         self.0.get(id).and_then(|t1| self.1.get(id).map(|t2| (t1, t2)))
@@ -68,10 +81,25 @@ impl<ID, T1, T2> Get2<&Option<Id<ID>>, T1, T2> for Comp2<ID, T1, T2> {
 }
 
 impl<ID, T1, T2> Comp2<ID, T1, T2> {
+    /// Inserts a pair of values for each id, reserving room for all of them first.
+    pub fn extend_from<I: Indexes<ID>>(&mut self, ids: impl IntoIterator<Item = I>, values: impl IntoIterator<Item = (T1, T2)>) {
+        let values = values.into_iter();
+        let additional = values.size_hint().0;
+        self.0.values.reserve(additional);
+        self.1.values.reserve(additional);
+        ids.into_iter()
+            .zip(values)
+            .for_each(|(id, value)| self.insert(id, value));
+    }
+
     pub fn len(&self) -> usize {
         self.0.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
     pub fn iter(&self) -> impl Iterator<Item = (&T1, &T2)> {
         self.0.iter().zip(self.1.iter())
     }
@@ -79,6 +107,20 @@ impl<ID, T1, T2> Comp2<ID, T1, T2> {
     pub fn iter_mut(&mut self) -> impl Iterator<Item = (&mut T1, &mut T2)> {
         self.0.iter_mut().zip(self.1.iter_mut())
     }
+
+    pub fn iter_alive<'a, A: Allocator<ID>>(&'a self, alloc: &'a A) -> impl Iterator<Item = (&'a T1, &'a T2)> + 'a {
+        self.0.iter_alive(alloc).zip(self.1.iter_alive(alloc))
+    }
+
+    pub fn iter_mut_alive<'a, A: Allocator<ID>>(&'a mut self, alloc: &'a A) -> impl Iterator<Item = (&'a mut T1, &'a mut T2)> + 'a {
+        self.0.iter_mut_alive(alloc).zip(self.1.iter_mut_alive(alloc))
+    }
+
+    pub fn iter_with_ids<'a, A: Allocator<ID>>(&'a self, alloc: &'a A) -> impl Iterator<Item = (A::Id, (&'a T1, &'a T2))> + 'a {
+        self.iter()
+            .enumerate()
+            .filter_map(move |(i, v)| alloc.live_id(i).map(|id| (id, v)))
+    }
 }
 
 impl<ID, T1: Copy, T2: Copy> Comp2<ID, T1, T2> {
@@ -102,7 +144,7 @@ impl<ID, T1: Copy, T2: Copy> Comp2<ID, T1, T2> {
 
     pub fn get_from_or<ID2>(&mut self, rhs: &Comp2<ID2, T1, T2>, ids: &Valid<ID, ID2>, fallback: (T1, T2)) {
         self.0.iter_mut()
-            .zip(ids.ids.iter())
+            .zip(ids.iter())
             .for_each(|(value, id)| {
                 *value = id.and_then(|id| rhs.0.get(id))
                     .copied()
@@ -110,7 +152,7 @@ impl<ID, T1: Copy, T2: Copy> Comp2<ID, T1, T2> {
             });
 
         self.1.iter_mut()
-            .zip(ids.ids.iter())
+            .zip(ids.iter())
             .for_each(|(value, id)| {
                 *value = id.and_then(|id| rhs.1.get(id))
                     .copied()
@@ -122,7 +164,7 @@ impl<ID, T1: Copy, T2: Copy> Comp2<ID, T1, T2> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::allocators::FixedAllocator;
+    use crate::allocators::{DynamicAllocator, FixedAllocator};
 
     #[test]
     fn zip_to_comp1() {
@@ -145,4 +187,38 @@ mod tests {
         assert_eq!(7, t1.0.values[0]);
         assert_eq!(9, t1.1.values[0]);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn iter_alive_skips_dead() {
+        let mut a = DynamicAllocator::<()>::default();
+
+        let mut t = Comp2::<(), u32, u32>::default();
+
+        let id0 = a.create();
+        let id1 = a.create();
+        t.insert(id0, (1, 2));
+        t.insert(id1, (3, 4));
+        a.kill(id0);
+
+        t.iter_mut_alive(&a).for_each(|(a, b)| *a += *b);
+
+        assert_eq!(vec![(&1, &2), (&7, &4)], t.iter().collect::<Vec<_>>());
+        assert_eq!(vec![(id1, (&7, &4))], t.iter_with_ids(&a).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn extend_from_reused_slots() {
+        let mut a = DynamicAllocator::<()>::default();
+        let mut t = Comp2::<(), u32, u32>::default();
+
+        let first = a.create_many(2);
+        t.extend_from(&first, vec![(1, 2), (3, 4)]);
+        a.kill(first[0]);
+
+        let ids = a.create_many(2);
+        t.extend_from(&ids, vec![(5, 6), (7, 8)]);
+
+        assert_eq!(vec![(&5, &6), (&3, &4), (&7, &8)], t.iter().collect::<Vec<_>>());
+        assert_eq!(Some((&7, &8)), t.get(Id::new(ids[1].index())));
+    }
+}
@@ -0,0 +1,236 @@
+use super::*;
+use crate::allocators::{Allocator, Indexes, Valid};
+use crate::{Get3, Get4, Get5, Get6, Get7, Get8};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+macro_rules! zip_nested {
+    ($only:expr) => { $only };
+    ($first:expr, $($rest:expr),+) => { $first.zip(zip_nested!($($rest),+)) };
+}
+
+macro_rules! pat_nested {
+    ($only:ident) => { $only };
+    ($first:ident, $($rest:ident),+) => { ($first, pat_nested!($($rest),+)) };
+}
+
+macro_rules! comp_n {
+    ($comp:ident, $get:ident, $(($t:ident, $i:tt, $v:ident)),+) => {
+        #[derive(Debug, Clone)]
+        pub struct $comp<ID, $($t),+>($(pub Comp1<ID, $t>),+);
+
+        impl<ID, $($t),+> Default for $comp<ID, $($t),+> {
+            fn default() -> Self {
+                Self($(Comp1::<ID, $t>::default()),+)
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl<ID, $($t: Serialize),+> Serialize for $comp<ID, $($t),+> {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                ($(&self.$i,)+).serialize(serializer)
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl<'de, ID, $($t: Deserialize<'de>),+> Deserialize<'de> for $comp<ID, $($t),+> {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let ($($v,)+) = <($(Comp1<ID, $t>,)+)>::deserialize(deserializer)?;
+                Ok(Self($($v),+))
+            }
+        }
+
+        impl<ID, $($t,)+ I: Indexes<ID>> Insert<I, ($($t,)+)> for $comp<ID, $($t),+> {
+            fn insert(&mut self, id: I, value: ($($t,)+)) {
+                $(self.$i.insert(id, value.$i);)+
+            }
+        }
+
+        impl<ID, $($t),+> Remove<DenseRow<ID>> for $comp<ID, $($t),+> {
+            fn remove(&mut self, removed: DenseRow<ID>) {
+                $(self.$i.remove(removed);)+
+            }
+        }
+
+        impl<ID, $($t),+> $get<Id<ID>, $($t),+> for $comp<ID, $($t),+> {
+            fn get(&self, id: Id<ID>) -> Option<($(&$t,)+)> {
+                Some(($(self.$i.get(id)?,)+))
+            }
+
+            fn get_mut(&mut self, id: Id<ID>) -> Option<($(&mut $t,)+)> {
+                Some(($(self.$i.get_mut(id)?,)+))
+            }
+        }
+
+        impl<ID, $($t),+> $get<&Id<ID>, $($t),+> for $comp<ID, $($t),+> {
+            fn get(&self, id: &Id<ID>) -> Option<($(&$t,)+)> {
+                self.get(*id)
+            }
+
+            fn get_mut(&mut self, id: &Id<ID>) -> Option<($(&mut $t,)+)> {
+                self.get_mut(*id)
+            }
+        }
+
+        impl<ID, $($t),+> $get<Option<Id<ID>>, $($t),+> for $comp<ID, $($t),+> {
+            fn get(&self, id: Option<Id<ID>>) -> Option<($(&$t,)+)> {
+                id.and_then(|id| self.get(id))
+            }
+
+            fn get_mut(&mut self, id: Option<Id<ID>>) -> Option<($(&mut $t,)+)> {
+                id.and_then(move |id| self.get_mut(id))
+            }
+        }
+
+        impl<ID, $($t),+> $get<&Option<Id<ID>>, $($t),+> for $comp<ID, $($t),+> {
+            fn get(&self, id: &Option<Id<ID>>) -> Option<($(&$t,)+)> {
+                id.and_then(|id| self.get(id))
+            }
+
+            fn get_mut(&mut self, id: &Option<Id<ID>>) -> Option<($(&mut $t,)+)> {
+                id.and_then(move |id| self.get_mut(id))
+            }
+        }
+
+        impl<ID, $($t),+> $comp<ID, $($t),+> {
+            pub fn len(&self) -> usize {
+                self.0.len()
+            }
+
+            pub fn is_empty(&self) -> bool {
+                self.0.is_empty()
+            }
+
+            pub fn iter(&self) -> impl Iterator<Item = ($(&$t,)+)> {
+                zip_nested!($(self.$i.iter()),+).map(|pat_nested!($($v),+)| ($($v,)+))
+            }
+
+            pub fn iter_mut(&mut self) -> impl Iterator<Item = ($(&mut $t,)+)> {
+                zip_nested!($(self.$i.iter_mut()),+).map(|pat_nested!($($v),+)| ($($v,)+))
+            }
+
+            pub fn iter_alive<'a, A: Allocator<ID>>(&'a self, alloc: &'a A) -> impl Iterator<Item = ($(&'a $t,)+)> + 'a {
+                self.iter()
+                    .enumerate()
+                    .filter(move |(i, _)| alloc.is_alive_index(*i))
+                    .map(|(_, v)| v)
+            }
+
+            pub fn iter_mut_alive<'a, A: Allocator<ID>>(&'a mut self, alloc: &'a A) -> impl Iterator<Item = ($(&'a mut $t,)+)> + 'a {
+                self.iter_mut()
+                    .enumerate()
+                    .filter(move |(i, _)| alloc.is_alive_index(*i))
+                    .map(|(_, v)| v)
+            }
+
+            pub fn iter_with_ids<'a, A: Allocator<ID>>(&'a self, alloc: &'a A) -> impl Iterator<Item = (A::Id, ($(&'a $t,)+))> + 'a {
+                self.iter()
+                    .enumerate()
+                    .filter_map(move |(i, v)| alloc.live_id(i).map(|id| (id, v)))
+            }
+        }
+
+        impl<ID, $($t: Copy),+> $comp<ID, $($t),+> {
+            pub fn get_from<ID2>(&mut self, rhs: &$comp<ID2, $($t),+>, ids: &Comp1<ID, Id<ID2>>) {
+                self.iter_mut()
+                    .zip(ids.iter())
+                    .for_each(|(($($v,)+), id)| {
+                        if let Some(values) = rhs.get(id) {
+                            $(*$v = *values.$i;)+
+                        }
+                    });
+            }
+
+            pub fn get_from_or<ID2>(&mut self, rhs: &$comp<ID2, $($t),+>, ids: &Valid<ID, ID2>, fallback: ($($t,)+)) {
+                self.iter_mut()
+                    .zip(ids.iter())
+                    .for_each(|(($($v,)+), id)| {
+                        let values = rhs.get(id)
+                            .map(|values| ($(*values.$i,)+))
+                            .unwrap_or(fallback);
+                        $(*$v = values.$i;)+
+                    });
+            }
+        }
+    };
+}
+
+comp_n!(Comp3, Get3, (T1, 0, a), (T2, 1, b), (T3, 2, c));
+comp_n!(Comp4, Get4, (T1, 0, a), (T2, 1, b), (T3, 2, c), (T4, 3, d));
+comp_n!(Comp5, Get5, (T1, 0, a), (T2, 1, b), (T3, 2, c), (T4, 3, d), (T5, 4, e));
+comp_n!(Comp6, Get6, (T1, 0, a), (T2, 1, b), (T3, 2, c), (T4, 3, d), (T5, 4, e), (T6, 5, f));
+comp_n!(Comp7, Get7, (T1, 0, a), (T2, 1, b), (T3, 2, c), (T4, 3, d), (T5, 4, e), (T6, 5, f), (T7, 6, g));
+comp_n!(Comp8, Get8, (T1, 0, a), (T2, 1, b), (T3, 2, c), (T4, 3, d), (T5, 4, e), (T6, 5, f), (T7, 6, g), (T8, 7, h));
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::allocators::{DynamicAllocator, FixedAllocator};
+    use crate::links::GenIds;
+
+    #[derive(Debug, Default)] struct Type1;
+    #[derive(Debug, Default)] struct Type2;
+
+    #[test]
+    fn insert_get_iter() {
+        let mut a = FixedAllocator::<()>::default();
+        let mut t = Comp3::<(), u32, u32, u32>::default();
+
+        let id0 = a.create();
+        let id1 = a.create();
+        t.insert(id0, (1, 2, 3));
+        t.insert(id1, (4, 5, 6));
+
+        assert_eq!(Some((&4, &5, &6)), t.get(id1));
+
+        t.iter_mut().for_each(|(x, y, z)| *z += *x + *y);
+        assert_eq!(vec![(&1, &2, &6), (&4, &5, &15)], t.iter().collect::<Vec<_>>());
+
+        if let Some((x, _, _)) = t.get_mut(id0) {
+            *x = 0;
+        }
+        assert_eq!(Some((&0, &2, &6)), t.get(&id0));
+    }
+
+    #[test]
+    fn get_from() {
+        let mut alloc1 = FixedAllocator::<Type1>::default();
+        let mut from = Comp4::<Type1, u8, u8, u8, u8>::default();
+        for i in 0..4 {
+            from.insert(alloc1.create(), (i, i + 1, i + 2, i + 3));
+        }
+
+        let mut alloc2 = FixedAllocator::<Type2>::default();
+        let mut to = Comp4::<Type2, u8, u8, u8, u8>::default();
+        let mut ids = Comp1::<Type2, Id<Type1>>::default();
+        let id = alloc2.create();
+        to.insert(id, Default::default());
+        ids.insert(id, Id::new(2));
+
+        to.get_from(&from, &ids);
+
+        assert_eq!(Some((&2, &3, &4, &5)), to.get(id));
+    }
+
+    #[test]
+    fn get_from_or() {
+        let mut alloc1 = DynamicAllocator::<Type1>::default();
+        let mut from = Comp3::<Type1, u8, u8, u8>::default();
+        let id1 = alloc1.create();
+        from.insert(id1, (1, 2, 3));
+
+        let mut alloc2 = FixedAllocator::<Type2>::default();
+        let mut to = Comp3::<Type2, u8, u8, u8>::default();
+        let mut links = GenIds::<Type2, Type1>::default();
+        let id2 = alloc2.create();
+        to.insert(id2, Default::default());
+        links.insert(id2, id1);
+
+        to.get_from_or(&from, &alloc1.validate(&mut links), (7, 8, 9));
+        assert_eq!(Some((&1, &2, &3)), to.get(id2));
+
+        alloc1.kill(id1);
+        to.get_from_or(&from, &alloc1.validate(&mut links), (7, 8, 9));
+        assert_eq!(Some((&7, &8, &9)), to.get(id2));
+    }
+}
@@ -1,9 +1,17 @@
-use crate::allocators::{Id, GenId};
-use crate::{Get1, Get2, Insert};
+use crate::allocators::{DenseRow, GenId, Id};
+use crate::{Get1, Get2, Insert, Remove};
 use std::marker::PhantomData;
 
-pub use comp1::Comp1;
+pub use aligned::{AlignedVec, ALIGN};
+pub use comp1::{Comp1, Chunks, ChunksMut, IndexGap};
 pub use comp2::Comp2;
+pub use comp_n::*;
+pub use sparse::SparseComp;
+pub use tracked::{Mut, Tracked};
 
+mod aligned;
 mod comp1;
-mod comp2;
\ No newline at end of file
+mod comp2;
+mod comp_n;
+mod sparse;
+mod tracked;
\ No newline at end of file
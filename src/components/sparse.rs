@@ -0,0 +1,332 @@
+use super::*;
+use crate::allocators::Indexes;
+#[cfg(all(feature = "branded", debug_assertions))]
+use crate::allocators::ColumnBrand;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Sparse-set column for components that only a few entities have.
+///
+/// `sparse` maps an entity index to a position in the packed `ids` and `values`, so lookups stay
+/// constant time while iteration only touches the entities that have the component.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(bound(serialize = "T: Serialize", deserialize = "T: Deserialize<'de>")))]
+pub struct SparseComp<ID, T> {
+    sparse: Vec<Option<usize>>,
+    ids: Vec<Id<ID>>,
+    values: Vec<T>,
+    #[cfg(all(feature = "branded", debug_assertions))]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    brand: ColumnBrand,
+}
+
+impl<ID, T> Default for SparseComp<ID, T> {
+    fn default() -> Self {
+        Self {
+            sparse: Default::default(),
+            ids: Default::default(),
+            values: Default::default(),
+            #[cfg(all(feature = "branded", debug_assertions))]
+            brand: Default::default(),
+        }
+    }
+}
+
+impl<ID, T, I: Indexes<ID>> Insert<I, T> for SparseComp<ID, T> {
+    fn insert(&mut self, id: I, value: T) {
+        self.bind(id);
+        let index = id.index();
+        if let Some(packed) = self.packed(index) {
+            self.values[packed] = value;
+            return;
+        }
+
+        if self.sparse.len() <= index {
+            self.sparse.resize(index + 1, None);
+        }
+        self.sparse[index] = Some(self.values.len());
+        self.ids.push(Id::new(index));
+        self.values.push(value);
+    }
+}
+
+impl<ID, T> Remove<DenseRow<ID>> for SparseComp<ID, T> {
+    /// Removes the value at the vacated row and renumbers the table's last row to it, matching the
+    /// swap-remove of the dense columns.
+    fn remove(&mut self, removed: DenseRow<ID>) {
+        self.check(removed.row());
+        let index = removed.row().index;
+        let last = removed.last().index;
+        self.resize(last + 1);
+
+        self.take(Id::<ID>::new(index));
+
+        if index < last {
+            if let Some(packed) = self.sparse[last] {
+                self.ids[packed] = Id::new(index);
+            }
+            self.sparse[index] = self.sparse[last];
+        }
+        self.sparse.pop();
+    }
+}
+
+impl<ID, T> Get1<Id<ID>, T> for SparseComp<ID, T> {
+    fn get(&self, id: Id<ID>) -> Option<&T> {
+        self.check(id);
+        self.packed(id.index).map(|packed| &self.values[packed])
+    }
+
+    fn get_mut(&mut self, id: Id<ID>) -> Option<&mut T> {
+        self.check(id);
+        self.packed(id.index).map(move |packed| &mut self.values[packed])
+    }
+}
+
+impl<ID, T> Get1<&Id<ID>, T> for SparseComp<ID, T> {
+    fn get(&self, id: &Id<ID>) -> Option<&T> {
+        self.get(*id)
+    }
+
+    fn get_mut(&mut self, id: &Id<ID>) -> Option<&mut T> {
+        self.get_mut(*id)
+    }
+}
+
+impl<ID, T> Get1<Option<Id<ID>>, T> for SparseComp<ID, T> {
+    fn get(&self, id: Option<Id<ID>>) -> Option<&T> {
+        id.and_then(|id| self.get(id))
+    }
+
+    fn get_mut(&mut self, id: Option<Id<ID>>) -> Option<&mut T> {
+        id.and_then(move |id| self.get_mut(id))
+    }
+}
+
+impl<ID, T> Get1<&Option<Id<ID>>, T> for SparseComp<ID, T> {
+    fn get(&self, id: &Option<Id<ID>>) -> Option<&T> {
+        id.and_then(|id| self.get(id))
+    }
+
+    fn get_mut(&mut self, id: &Option<Id<ID>>) -> Option<&mut T> {
+        id.and_then(move |id| self.get_mut(id))
+    }
+}
+
+impl<ID, T> SparseComp<ID, T> {
+    fn packed(&self, index: usize) -> Option<usize> {
+        self.sparse.get(index).copied().flatten()
+    }
+
+    /// Ties the column to the allocator of the first branded id written to it.
+    #[cfg_attr(not(all(feature = "branded", debug_assertions)), allow(unused_variables))]
+    fn bind<I: Indexes<ID>>(&mut self, id: I) {
+        #[cfg(all(feature = "branded", debug_assertions))]
+        self.brand.bind::<ID>(id.brand());
+    }
+
+    #[cfg_attr(not(all(feature = "branded", debug_assertions)), allow(unused_variables))]
+    fn check<I: Indexes<ID>>(&self, id: I) {
+        #[cfg(all(feature = "branded", debug_assertions))]
+        self.brand.check::<ID>(id.brand());
+    }
+
+    pub fn contains<I: Indexes<ID>>(&self, id: I) -> bool {
+        self.check(id);
+        self.packed(id.index()).is_some()
+    }
+
+    /// Removes and returns the entity's value, moving the last packed value into its place.
+    ///
+    /// Unlike the `Remove` trait this only affects `id`: no row is renumbered.
+    pub fn take<I: Indexes<ID>>(&mut self, id: I) -> Option<T> {
+        self.check(id);
+        let index = id.index();
+        let packed = self.packed(index)?;
+
+        self.sparse[index] = None;
+        self.ids.swap_remove(packed);
+        let value = self.values.swap_remove(packed);
+
+        if let Some(moved) = self.ids.get(packed) {
+            self.sparse[moved.index] = Some(packed);
+        }
+
+        Some(value)
+    }
+
+    /// Sets the number of rows the column covers, dropping the values of rows past the end.
+    pub fn resize(&mut self, rows: usize) {
+        for index in rows..self.sparse.len() {
+            self.take(Id::<ID>::new(index));
+        }
+        self.sparse.resize(rows, None);
+    }
+
+    pub fn clear(&mut self) {
+        self.sparse.clear();
+        self.ids.clear();
+        self.values.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn ids(&self) -> &[Id<ID>] {
+        &self.ids
+    }
+
+    pub fn values(&self) -> &[T] {
+        &self.values
+    }
+
+    pub fn values_mut(&mut self) -> &mut [T] {
+        &mut self.values
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Id<ID>, &T)> {
+        self.ids.iter().copied().zip(self.values.iter())
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Id<ID>, &mut T)> {
+        self.ids.iter().copied().zip(self.values.iter_mut())
+    }
+
+    /// Pairs each sparse value with the entity's value in a dense column, skipping entities the
+    /// column has no row for. Cost is proportional to the sparse side only.
+    pub fn join<'a, U>(&'a self, dense: &'a Comp1<ID, U>) -> impl Iterator<Item = (Id<ID>, &'a T, &'a U)> + 'a {
+        self.iter()
+            .filter_map(move |(id, value)| dense.get(id).map(|other| (id, value, other)))
+    }
+
+    /// Like `join`, with mutable access to the dense column.
+    pub fn join_mut<U>(&self, dense: &mut Comp1<ID, U>, mut f: impl FnMut(Id<ID>, &T, &mut U)) {
+        for (id, value) in self.iter() {
+            if let Some(other) = dense.get_mut(id) {
+                f(id, value, other);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::allocators::{DynamicAllocator, FixedAllocator};
+
+    #[test]
+    fn insert_get_remove() {
+        let mut a = FixedAllocator::<()>::default();
+        let ids = (0..4).map(|_| a.create()).collect::<Vec<_>>();
+
+        let mut tags = SparseComp::<(), u32>::default();
+        tags.insert(ids[3], 3);
+        tags.insert(ids[1], 1);
+        tags.insert(ids[1], 10);
+
+        assert_eq!(2, tags.len());
+        assert_eq!(None, tags.get(ids[0]));
+        assert_eq!(Some(&10), tags.get(ids[1]));
+        assert_eq!(Some(&3), tags.get(ids[3]));
+
+        assert_eq!(Some(3), tags.take(ids[3]));
+        assert_eq!(None, tags.take(ids[3]));
+        assert!(!tags.contains(ids[3]));
+        assert_eq!(vec![(ids[1], &10)], tags.iter().collect::<Vec<_>>());
+    }
+
+    #[cfg(all(feature = "branded", debug_assertions))]
+    #[test]
+    #[should_panic(expected = "used with a column of allocator #")]
+    fn branded_id_from_other_allocator_panics() {
+        let mut world1 = FixedAllocator::<()>::default();
+        let mut world2 = FixedAllocator::<()>::default();
+        let mut tags = SparseComp::<(), u32>::default();
+
+        tags.insert(world1.create(), 1);
+        tags.contains(world2.create());
+    }
+
+    #[test]
+    fn take_moves_last_packed_value() {
+        let mut tags = SparseComp::<(), u32>::default();
+        (0..3).for_each(|i| tags.insert(Id::<()>::new(i), i as u32));
+
+        tags.take(Id::<()>::new(0));
+
+        assert_eq!(Some(&2), tags.get(Id::new(2)));
+        assert_eq!(Some(&1), tags.get(Id::new(1)));
+        assert_eq!(&[Id::new(2), Id::new(1)], tags.ids());
+    }
+
+    #[test]
+    fn remove_follows_compacted_rows() {
+        #[derive(Default)]
+        struct Table {
+            mass: Comp1<(), f64>,
+            tag: SparseComp<(), u32>,
+        }
+
+        impl Remove<DenseRow<()>> for Table {
+            fn remove(&mut self, removed: DenseRow<()>) {
+                self.mass.remove(removed);
+                self.tag.remove(removed);
+            }
+        }
+
+        let mut a = DynamicAllocator::<()>::compacting();
+        let mut table = Table::default();
+        let ids = a.create_many(4);
+        for (i, id) in ids.iter().enumerate() {
+            table.mass.insert(a.row(id).unwrap(), i as f64);
+        }
+        table.tag.insert(a.row(&ids[0]).unwrap(), 0);
+        table.tag.insert(a.row(&ids[2]).unwrap(), 2);
+
+        a.kill_and_remove(ids[0], &mut table);
+        a.kill_and_remove(ids[1], &mut table);
+
+        let tag = |id| table.tag.get(a.row(id).unwrap());
+        assert_eq!(Some(&2), tag(&ids[2]));
+        assert_eq!(None, tag(&ids[3]));
+        assert_eq!(1, table.tag.len());
+    }
+
+    #[test]
+    fn remove_moves_the_table_last_row_past_the_sparse_end() {
+        let mut a = DynamicAllocator::<()>::compacting();
+        let ids = a.create_many(4);
+        let mut tags = SparseComp::<(), u32>::default();
+        tags.insert(a.row(&ids[0]).unwrap(), 0);
+        tags.insert(a.row(&ids[1]).unwrap(), 1);
+
+        a.kill_and_remove(ids[0], &mut tags);
+
+        assert_eq!(Some(&1), tags.get(a.row(&ids[1]).unwrap()));
+        assert_eq!(None, tags.get(a.row(&ids[3]).unwrap()));
+        assert_eq!(&[Id::new(1)], tags.ids());
+    }
+
+    #[test]
+    fn join_with_dense() {
+        let mut a = FixedAllocator::<()>::default();
+        let ids = (0..4).map(|_| a.create()).collect::<Vec<_>>();
+
+        let mut mass = Comp1::<(), f64>::default();
+        ids.iter().enumerate().for_each(|(i, id)| mass.insert(id, i as f64));
+
+        let mut shipyard = SparseComp::<(), u32>::default();
+        shipyard.insert(ids[2], 7);
+        shipyard.insert(Id::<()>::new(9), 9);
+
+        assert_eq!(vec![(ids[2], &7, &2.0)], shipyard.join(&mass).collect::<Vec<_>>());
+
+        shipyard.join_mut(&mut mass, |_, level, mass| *mass += *level as f64);
+        assert_eq!(vec![0.0, 1.0, 9.0, 3.0], mass.values);
+    }
+}
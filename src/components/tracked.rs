@@ -0,0 +1,257 @@
+use super::*;
+use crate::allocators::{Allocator, Indexes};
+use std::ops::{Deref, DerefMut};
+
+/// A column that remembers the tick at which each row was last written.
+///
+/// Writes through `insert`, `get_mut` and `iter_mut` are stamped with the current tick. Call
+/// `advance` once per update so readers can pass the tick they last saw to `changed_since`.
+#[derive(Debug, Clone)]
+pub struct Tracked<C> {
+    column: C,
+    ticks: Vec<u64>,
+    tick: u64,
+}
+
+impl<ID, T> Default for Tracked<Comp1<ID, T>> {
+    fn default() -> Self {
+        Self::new(Comp1::default())
+    }
+}
+
+/// Mutable access to a tracked value that marks its row as changed when written.
+#[derive(Debug)]
+pub struct Mut<'a, T> {
+    value: &'a mut T,
+    changed: &'a mut u64,
+    tick: u64,
+}
+
+impl<T> Deref for Mut<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value
+    }
+}
+
+impl<T> DerefMut for Mut<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        *self.changed = self.tick;
+        self.value
+    }
+}
+
+impl<ID, T, I: Indexes<ID>> Insert<I, T> for Tracked<Comp1<ID, T>> {
+    fn insert(&mut self, id: I, value: T) {
+        let index = id.index();
+        self.column.insert(id, value);
+
+        if index < self.column.len() {
+            self.ticks.resize(self.column.len(), 0);
+            self.ticks[index] = self.tick;
+        }
+    }
+}
+
+impl<ID, T> Remove<DenseRow<ID>> for Tracked<Comp1<ID, T>> {
+    /// Stamps the row the last row moves into, since a different entity now owns it.
+    fn remove(&mut self, removed: DenseRow<ID>) {
+        let index = removed.row().index;
+        if index < self.ticks.len() {
+            self.column.remove(removed);
+            self.ticks.swap_remove(index);
+
+            if let Some(moved) = self.ticks.get_mut(index) {
+                *moved = self.tick;
+            }
+        }
+    }
+}
+
+impl<ID, T> Get1<Id<ID>, T> for Tracked<Comp1<ID, T>> {
+    fn get(&self, id: Id<ID>) -> Option<&T> {
+        self.column.get(id)
+    }
+
+    /// Marks the row as changed, whether or not the value is written.
+    fn get_mut(&mut self, id: Id<ID>) -> Option<&mut T> {
+        let value = self.column.get_mut(id)?;
+        self.ticks[id.index] = self.tick;
+        Some(value)
+    }
+}
+
+impl<ID, T> Get1<&Id<ID>, T> for Tracked<Comp1<ID, T>> {
+    fn get(&self, id: &Id<ID>) -> Option<&T> {
+        self.get(*id)
+    }
+
+    fn get_mut(&mut self, id: &Id<ID>) -> Option<&mut T> {
+        self.get_mut(*id)
+    }
+}
+
+impl<ID, T> Tracked<Comp1<ID, T>> {
+    pub fn new(column: Comp1<ID, T>) -> Self {
+        Self {
+            ticks: vec![1; column.len()],
+            column,
+            tick: 1,
+        }
+    }
+
+    /// The tick that writes are currently stamped with.
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// Ends the current tick and returns it. Later writes are newer than the returned tick.
+    pub fn advance(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick - 1
+    }
+
+    pub fn column(&self) -> &Comp1<ID, T> {
+        &self.column
+    }
+
+    pub fn into_inner(self) -> Comp1<ID, T> {
+        self.column
+    }
+
+    pub fn len(&self) -> usize {
+        self.column.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.column.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.column.iter()
+    }
+
+    /// Only rows that are written through the returned guards are marked as changed.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = Mut<'_, T>> {
+        let tick = self.tick;
+        self.column
+            .iter_mut()
+            .zip(self.ticks.iter_mut())
+            .map(move |(value, changed)| Mut { value, changed, tick })
+    }
+
+    /// Like `get_mut`, but only marks the row if the value is written.
+    pub fn get_tracked(&mut self, id: Id<ID>) -> Option<Mut<'_, T>> {
+        let tick = self.tick;
+        let value = self.column.values.get_mut(id.index)?;
+        let changed = &mut self.ticks[id.index];
+        Some(Mut { value, changed, tick })
+    }
+
+    pub fn changed_at(&self, id: Id<ID>) -> Option<u64> {
+        self.ticks.get(id.index).copied()
+    }
+
+    /// The rows written after `tick`. These are column rows, which only equal entity ids for
+    /// allocators that do not compact; use `changed_since_for` to get the ids.
+    pub fn changed_since(&self, tick: u64) -> impl Iterator<Item = (Id<ID>, &T)> {
+        self.ticks
+            .iter()
+            .zip(self.column.iter())
+            .enumerate()
+            .filter(move |(_, (changed, _))| **changed > tick)
+            .map(|(i, (_, value))| (Id::new(i), value))
+    }
+
+    /// The live entities whose rows were written after `tick`.
+    pub fn changed_since_for<'a, A: Allocator<ID>>(&'a self, alloc: &'a A, tick: u64) -> impl Iterator<Item = (A::Id, &'a T)> + 'a {
+        self.changed_since(tick)
+            .filter_map(move |(row, value)| alloc.live_id(row.index).map(|id| (id, value)))
+    }
+}
+
+impl<ID1, T: Copy> Comp1<ID1, T> {
+    /// Like `get_from`, but only copies into the rows whose source value changed after `tick`.
+    pub fn get_from_changed<ID2>(&mut self, rhs: &Tracked<Comp1<ID2, T>>, ids: &Comp1<ID1, Id<ID2>>, tick: u64) {
+        self.iter_mut()
+            .zip(ids.iter())
+            .filter(|(_, id)| rhs.changed_at(**id).is_some_and(|changed| changed > tick))
+            .for_each(|(value, id)| {
+                if let Some(v) = rhs.get(id) {
+                    *value = *v;
+                }
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::allocators::{DynamicAllocator, FixedAllocator};
+
+    #[derive(Debug, Default)]
+    struct Type1;
+
+    #[test]
+    fn changed_since_reports_written_rows() {
+        let mut a = FixedAllocator::<Type1>::default();
+        let mut values = Tracked::<Comp1<Type1, u32>>::default();
+        let ids = (0..4).map(|i| {
+            let id = a.create();
+            values.insert(id, i);
+            id
+        }).collect::<Vec<_>>();
+
+        let seen = values.advance();
+        assert_eq!(0, values.changed_since(seen).count());
+
+        *values.get_mut(ids[1]).unwrap() += 10;
+        values.iter_mut().filter(|v| **v == 3).for_each(|mut v| *v = 30);
+        let _ = values.get_tracked(ids[2]).map(|v| *v);
+
+        assert_eq!(vec![(ids[1], &11), (ids[3], &30)], values.changed_since(seen).collect::<Vec<_>>());
+
+        let seen = values.advance();
+        values.insert(ids[0], 5);
+        assert_eq!(vec![(ids[0], &5)], values.changed_since(seen).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn changed_since_for_maps_rows_to_ids() {
+        let mut a = DynamicAllocator::<Type1>::compacting();
+        let mut values = Tracked::<Comp1<Type1, u32>>::default();
+        let ids = a.create_many(3);
+        for (i, id) in ids.iter().enumerate() {
+            values.insert(a.row(id).unwrap(), i as u32);
+        }
+
+        a.kill_and_remove(ids[0], &mut values);
+        let seen = values.advance();
+        *values.get_mut(a.row(&ids[2]).unwrap()).unwrap() += 10;
+
+        assert_eq!(vec![(Id::new(0), &12)], values.changed_since(seen).collect::<Vec<_>>());
+        assert_eq!(vec![(ids[2], &12)], values.changed_since_for(&a, seen).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn get_from_changed_skips_unchanged_rows() {
+        let mut source = Tracked::<Comp1<Type1, u32>>::default();
+        source.insert(Id::<Type1>::new(0), 1);
+        source.insert(Id::<Type1>::new(1), 2);
+
+        let mut links = Comp1::<(), Id<Type1>>::default();
+        links.insert(Id::<()>::new(0), Id::new(1));
+        links.insert(Id::<()>::new(1), Id::new(0));
+
+        let mut target = Comp1::<(), u32>::default();
+        target.insert(Id::<()>::new(0), 0);
+        target.insert(Id::<()>::new(1), 0);
+
+        let seen = source.advance();
+        *source.get_mut(Id::new(0)).unwrap() = 7;
+        target.get_from_changed(&source, &links, seen);
+
+        assert_eq!(vec![0, 7], target.values);
+    }
+}
@@ -0,0 +1,189 @@
+use crate::allocators::Id;
+use crate::components::Comp1;
+use std::fmt;
+use std::ops::Add;
+
+/// Entities of one type ordered so that every parent comes before its children.
+#[derive(Debug, Clone)]
+pub struct Hierarchy<ID> {
+    order: Vec<Id<ID>>,
+}
+
+impl<ID> Default for Hierarchy<ID> {
+    fn default() -> Self {
+        Self {
+            order: Default::default(),
+        }
+    }
+}
+
+impl<ID> Hierarchy<ID> {
+    pub fn new(parents: &Comp1<ID, Option<Id<ID>>>) -> Result<Self, HierarchyError<ID>> {
+        let mut hierarchy = Self::default();
+        hierarchy.rebuild(parents)?;
+        Ok(hierarchy)
+    }
+
+    /// Re-sorts after the parent column has changed. On error the previous order is kept.
+    pub fn rebuild(&mut self, parents: &Comp1<ID, Option<Id<ID>>>) -> Result<(), HierarchyError<ID>> {
+        let len = parents.len();
+        let mut children = vec![vec![]; len];
+        let mut order = Vec::with_capacity(len);
+
+        for (i, parent) in parents.iter().enumerate() {
+            match parent {
+                Some(parent) if parent.index >= len => return Err(HierarchyError::MissingParent(Id::new(i))),
+                Some(parent) => children[parent.index].push(i),
+                None => order.push(Id::new(i)),
+            }
+        }
+
+        let mut next = 0;
+        while let Some(id) = order.get(next) {
+            let id = id.index;
+            order.extend(children[id].iter().map(|child| Id::new(*child)));
+            next += 1;
+        }
+
+        if order.len() < len {
+            let mut sorted = vec![false; len];
+            order.iter().for_each(|id| sorted[id.index] = true);
+
+            // An unsorted entity never reaches a root, so after `len` steps up its parent chain
+            // it must be going around the cycle itself rather than hanging below it.
+            let mut cyclic = sorted.iter().position(|sorted| !sorted).unwrap_or_default();
+            for _ in 0..len {
+                match parents.values[cyclic] {
+                    Some(parent) => cyclic = parent.index,
+                    None => break,
+                }
+            }
+            return Err(HierarchyError::Cycle(Id::new(cyclic)));
+        }
+
+        self.order = order;
+        Ok(())
+    }
+
+    pub fn order(&self) -> &[Id<ID>] {
+        &self.order
+    }
+
+    /// Writes `local + global[parent]` into `global`, so each value includes all of its ancestors.
+    pub fn propagate<T>(&self, parents: &Comp1<ID, Option<Id<ID>>>, local: &Comp1<ID, T>, global: &mut Comp1<ID, T>)
+    where
+        T: Copy + Add<Output = T>,
+    {
+        global.values.clear();
+        global.values.extend_from_slice(&local.values);
+
+        for id in &self.order {
+            if let (Some(Some(parent)), Some(value)) = (parents.values.get(id.index), local.values.get(id.index)) {
+                if let Some(parent) = global.values.get(parent.index).copied() {
+                    global.values[id.index] = parent + *value;
+                }
+            }
+        }
+    }
+
+    /// Writes the sum of all ancestors' `local` values into `out`, excluding the entity's own value.
+    pub fn accumulate_ancestors<T>(&self, parents: &Comp1<ID, Option<Id<ID>>>, local: &Comp1<ID, T>, out: &mut Comp1<ID, T>)
+    where
+        T: Copy + Default + Add<Output = T>,
+    {
+        out.values.clear();
+        out.values.resize(parents.len(), T::default());
+
+        for id in &self.order {
+            if let Some(Some(parent)) = parents.values.get(id.index) {
+                if let Some(value) = local.values.get(parent.index) {
+                    out.values[id.index] = out.values[parent.index] + *value;
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum HierarchyError<ID> {
+    Cycle(Id<ID>),
+    MissingParent(Id<ID>),
+}
+
+impl<ID> fmt::Display for HierarchyError<ID> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HierarchyError::Cycle(id) => write!(f, "entity {} is part of a parent cycle", id.index),
+            HierarchyError::MissingParent(id) => write!(f, "entity {} has a parent that does not exist", id.index),
+        }
+    }
+}
+
+impl<ID: fmt::Debug> std::error::Error for HierarchyError<ID> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::allocators::FixedAllocator;
+    use crate::Insert;
+
+    fn tree() -> (Vec<Id<()>>, Comp1<(), Option<Id<()>>>) {
+        let mut a = FixedAllocator::<()>::default();
+        let ids = (0..5).map(|_| a.create()).collect::<Vec<_>>();
+
+        // 0 <- 3 <- 1, 0 <- 2, 4
+        let mut parents = Comp1::default();
+        parents.insert(ids[0], None);
+        parents.insert(ids[1], Some(ids[3]));
+        parents.insert(ids[2], Some(ids[0]));
+        parents.insert(ids[3], Some(ids[0]));
+        parents.insert(ids[4], None);
+
+        (ids, parents)
+    }
+
+    #[test]
+    fn parents_before_children() {
+        let (ids, parents) = tree();
+        let hierarchy = Hierarchy::new(&parents).unwrap();
+
+        let position = |id: Id<()>| hierarchy.order().iter().position(|o| *o == id).unwrap();
+        assert_eq!(5, hierarchy.order().len());
+        assert!(position(ids[0]) < position(ids[3]));
+        assert!(position(ids[3]) < position(ids[1]));
+        assert!(position(ids[0]) < position(ids[2]));
+    }
+
+    #[test]
+    fn propagate_and_accumulate() {
+        let (_, parents) = tree();
+        let hierarchy = Hierarchy::new(&parents).unwrap();
+
+        let mut local = Comp1::<(), u32>::default();
+        local.values = vec![1, 10, 100, 1000, 10000].into();
+
+        let mut global = Comp1::default();
+        hierarchy.propagate(&parents, &local, &mut global);
+        assert_eq!(vec![1, 1011, 101, 1001, 10000], global.values);
+
+        let mut ancestors = Comp1::default();
+        hierarchy.accumulate_ancestors(&parents, &local, &mut ancestors);
+        assert_eq!(vec![0, 1001, 1, 1, 0], ancestors.values);
+    }
+
+    #[test]
+    fn detects_cycles() {
+        let (ids, mut parents) = tree();
+        parents.insert(ids[0], Some(ids[1]));
+
+        assert_eq!(HierarchyError::Cycle(ids[3]), Hierarchy::new(&parents).unwrap_err());
+
+        let (ids, mut parents) = tree();
+        parents.insert(ids[3], Some(ids[4]));
+        parents.insert(ids[4], Some(ids[3]));
+        assert_eq!(HierarchyError::Cycle(ids[3]), Hierarchy::new(&parents).unwrap_err());
+
+        parents.values.push(Some(Id::new(9)));
+        assert_eq!(HierarchyError::MissingParent(Id::new(5)), Hierarchy::new(&parents).unwrap_err());
+    }
+}
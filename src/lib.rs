@@ -1,7 +1,21 @@
 pub mod allocators;
+pub mod bitset;
+pub mod commands;
 pub mod components;
+pub mod hierarchy;
 pub mod links;
+#[cfg(feature = "rayon")]
+pub mod parallel;
+pub mod prefab;
 pub mod prelude;
+pub mod query;
+pub mod schedule;
+pub mod simd;
+pub mod snapshot;
+pub mod world;
+
+#[cfg(feature = "derive")]
+pub use simd_ecs_derive::Table;
 
 pub trait Insert<ID, T> {
     fn insert(&mut self, id: ID, value: T);
@@ -15,4 +29,124 @@ pub trait Get1<ID, T> {
 pub trait Get2<ID, T1, T2> {
     fn get(&self, id: ID) -> Option<(&T1, &T2)>;
     fn get_mut(&mut self, id: ID) -> Option<(&mut T1, &mut T2)>;
-}
\ No newline at end of file
+}
+
+macro_rules! get_n {
+    ($get:ident, $($t:ident),+) => {
+        pub trait $get<ID, $($t),+> {
+            fn get(&self, id: ID) -> Option<($(&$t,)+)>;
+            fn get_mut(&mut self, id: ID) -> Option<($(&mut $t,)+)>;
+        }
+    };
+}
+
+get_n!(Get3, T1, T2, T3);
+get_n!(Get4, T1, T2, T3, T4);
+get_n!(Get5, T1, T2, T3, T4, T5);
+get_n!(Get6, T1, T2, T3, T4, T5, T6);
+get_n!(Get7, T1, T2, T3, T4, T5, T6, T7);
+get_n!(Get8, T1, T2, T3, T4, T5, T6, T7, T8);
+
+/// Removes the value at a vacated row by moving the value of the last row into its place.
+///
+/// Columns only implement this for `DenseRow`, which only a compacting allocator's
+/// `kill_and_remove` hands out. Moving a row anywhere else would leave the allocator pointing
+/// the moved entity at the wrong row.
+pub trait Remove<ID> {
+    fn remove(&mut self, id: ID);
+}
+
+impl<ID, R: Remove<ID>> Remove<ID> for &mut R {
+    fn remove(&mut self, id: ID) {
+        (**self).remove(id);
+    }
+}
+
+macro_rules! remove_tuple {
+    ($($t:ident $i:tt),+) => {
+        impl<ID: Copy, $($t: Remove<ID>),+> Remove<ID> for ($($t,)+) {
+            fn remove(&mut self, id: ID) {
+                $(self.$i.remove(id);)+
+            }
+        }
+    };
+}
+
+remove_tuple!(A 0);
+remove_tuple!(A 0, B 1);
+remove_tuple!(A 0, B 1, C 2);
+remove_tuple!(A 0, B 1, C 2, D 3);
+remove_tuple!(A 0, B 1, C 2, D 3, E 4);
+remove_tuple!(A 0, B 1, C 2, D 3, E 4, F 5);
+remove_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+remove_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
+
+/// A struct of component columns that can be filled from a row type, usually generated with `#[derive(Table)]`.
+pub trait Table: Sized {
+    type Row;
+
+    fn insert_row<I: allocators::Indexes<Self>>(&mut self, id: I, row: Self::Row);
+
+    /// A copy of the row at `id`, or `None` if any column is missing it. Tables whose rows cannot
+    /// be copied keep this default, which always returns `None`; derived tables implement it with
+    /// `#[table(clone)]`.
+    fn clone_row(&self, _id: allocators::Id<Self>) -> Option<Self::Row> {
+        None
+    }
+
+    /// Creates a new entity with a copy of the components of `id`. Links from the row to `id`
+    /// itself are remapped to the new entity. Links held in separate tables such as `GenIds` are
+    /// not copied; use `Prefab::instantiate_with_links` for those.
+    fn clone_entity<A>(&mut self, alloc: &mut A, id: A::Id) -> Option<A::Id>
+    where
+        A: allocators::Allocator<Self>,
+        A::Id: Eq + std::hash::Hash,
+        Self::Row: Clone + prefab::Remap<A::Id>,
+    {
+        let prefab = prefab::Prefab::capture(self, alloc, Some(id))?;
+        prefab.instantiate(self, alloc).get(&id)
+    }
+
+    /// The name and length of every column.
+    fn column_lengths(&self) -> Vec<(&'static str, usize)>;
+
+    /// Reports every column whose length differs from the number of rows in `alloc`.
+    fn check_lengths<A: allocators::Allocator<Self>>(&self, alloc: &A) -> Result<(), ColumnLengthError> {
+        let rows = alloc.rows();
+        let columns = self.column_lengths()
+            .into_iter()
+            .filter(|(_, len)| *len != rows)
+            .collect::<Vec<_>>();
+
+        if columns.is_empty() {
+            Ok(())
+        } else {
+            Err(ColumnLengthError {
+                table: std::any::type_name::<Self>(),
+                rows,
+                columns,
+            })
+        }
+    }
+}
+
+/// Columns of a table whose lengths disagree with its allocator.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ColumnLengthError {
+    pub table: &'static str,
+    pub rows: usize,
+    pub columns: Vec<(&'static str, usize)>,
+}
+
+impl std::fmt::Display for ColumnLengthError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} has {} rows but", self.table, self.rows)?;
+        for (i, (name, len)) in self.columns.iter().enumerate() {
+            let separator = if i == 0 { "" } else { "," };
+            write!(f, "{} column `{}` has {}", separator, name, len)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ColumnLengthError {}
@@ -1,45 +1,391 @@
 use crate::allocators::*;
-use crate::{Insert, Get1};
-use crate::components::Comp2;
+use crate::{Insert, Get1, Get2, Remove};
+use crate::components::{Comp1, Comp2};
+use crate::prefab::{IdMap, Remap};
+use std::hash::Hash;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Default, Clone)]
-pub struct GenIds<FROM, TO> {
-    pub ids: Comp2<FROM, Option<Id<TO>>, Option<Gen>>,
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(bound = ""))]
+pub struct GenIds<FROM, TO, G: Generation = u32> {
+    pub ids: Comp2<FROM, Option<Id<TO>>, Option<Gen<G>>>,
     pub version: u64,
+    referrers: Option<Referrers<FROM, TO>>,
 }
 
-impl<FROM, TO, I: Indexes<FROM>> Insert<I, GenId<TO>> for GenIds<FROM, TO> {
-    fn insert(&mut self, id: I, value: GenId<TO>) {
-        self.ids.insert(id, Some(value));
+/// The link rows that point at each target slot, and how far the allocator's events have been read.
+///
+/// `inserted` holds rows linked since the last update, whose targets may already have been dead
+/// when they were inserted and so will not show up in future events.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(bound = ""))]
+struct Referrers<FROM, TO> {
+    rows: Comp1<TO, Vec<Id<FROM>>>,
+    inserted: Vec<Id<FROM>>,
+    created: EventCursor,
+    killed: EventCursor,
+}
+
+impl<FROM, TO> Default for Referrers<FROM, TO> {
+    fn default() -> Self {
+        Self {
+            rows: Default::default(),
+            inserted: Default::default(),
+            created: Default::default(),
+            killed: Default::default(),
+        }
+    }
+}
+
+impl<FROM, TO> Referrers<FROM, TO> {
+    fn link(&mut self, target: Id<TO>, row: Id<FROM>) {
+        if self.rows.len() <= target.index {
+            self.rows.resize_with(target.index + 1, Vec::new);
+        }
+        self.rows.values[target.index].push(row);
+    }
+
+    fn unlink(&mut self, target: Id<TO>, row: Id<FROM>) {
+        if let Some(rows) = self.rows.get_mut(target) {
+            if let Some(position) = rows.iter().position(|r| *r == row) {
+                rows.swap_remove(position);
+            }
+        }
+    }
+
+    fn rename(&mut self, target: Id<TO>, from: Id<FROM>, to: Id<FROM>) {
+        if let Some(row) = self.rows.get_mut(target).and_then(|rows| rows.iter_mut().find(|r| **r == from)) {
+            *row = to;
+        }
+    }
+}
+
+impl<FROM, TO, G: Generation, I: Indexes<FROM>> Insert<I, GenId<TO, G>> for GenIds<FROM, TO, G> {
+    fn insert(&mut self, id: I, value: GenId<TO, G>) {
+        self.insert(id, Some(value));
     }
 }
 
-impl<FROM, TO, I: Indexes<FROM>> Insert<I, Option<GenId<TO>>> for GenIds<FROM, TO> {
-    fn insert(&mut self, id: I, value: Option<GenId<TO>>) {
+impl<FROM, TO, G: Generation, I: Indexes<FROM>> Insert<I, Option<GenId<TO, G>>> for GenIds<FROM, TO, G> {
+    fn insert(&mut self, id: I, value: Option<GenId<TO, G>>) {
+        if let Some(referrers) = &mut self.referrers {
+            let row = Id::new(id.index());
+            if row.index > self.ids.len() {
+                return;
+            }
+            if let Some(Some(old)) = self.ids.0.values.get(row.index) {
+                referrers.unlink(*old, row);
+            }
+            if let Some(new) = value {
+                referrers.link(new.index, row);
+                referrers.inserted.push(row);
+            }
+        }
         self.ids.insert(id, value);
     }
 }
 
-impl<FROM, TO> GenIds<FROM, TO> {
-    pub fn update(&mut self, alloc: &DynamicAllocator<TO>) {
+impl<FROM, TO, G: Generation> Remove<DenseRow<FROM>> for GenIds<FROM, TO, G> {
+    fn remove(&mut self, removed: DenseRow<FROM>) {
+        if let Some(referrers) = &mut self.referrers {
+            let row = Id::new(removed.row().index);
+            let last = Id::new(self.ids.len().wrapping_sub(1));
+            if let Some(Some(target)) = self.ids.0.values.get(row.index) {
+                referrers.unlink(*target, row);
+            }
+            if row.index < last.index {
+                if let Some(Some(target)) = self.ids.0.values.get(last.index) {
+                    referrers.rename(*target, last, row);
+                }
+            }
+        }
+        self.ids.remove(removed);
+    }
+}
+
+impl<FROM, TO, G: Generation> GenIds<FROM, TO, G> {
+    /// Creates links that also index which rows point at each target. With an allocator that
+    /// records events, `update` then only revisits the rows pointing at created or killed slots.
+    ///
+    /// Rows must be written through `Insert` and `Remove` rather than `ids` to keep the index current.
+    pub fn indexed() -> Self {
+        Self {
+            ids: Default::default(),
+            version: Default::default(),
+            referrers: Some(Default::default()),
+        }
+    }
+
+    pub fn update(&mut self, alloc: &DynamicAllocator<TO, G>) {
         if alloc.version != self.version {
-            self.remove_invalid_indices(&alloc);
+            if !self.update_from_events(alloc) {
+                self.remove_invalid_indices(alloc);
+                self.rebuild_referrers(alloc);
+            }
             self.version = alloc.version;
         }
     }
 
-    fn remove_invalid_indices(&mut self, alloc: &DynamicAllocator<TO>) {
+    /// The target linked from `row`, if any.
+    pub fn target(&self, row: Id<FROM>) -> Option<GenId<TO, G>> {
+        match Get2::get(&self.ids, row)? {
+            (Some(index), Some(gen)) => Some(GenId::new(*index, *gen)),
+            _ => None,
+        }
+    }
+
+    /// Copies the link of every cloned row in `rows` to its clone. Targets that were cloned
+    /// along with them, listed in `targets`, are remapped to their clones.
+    pub fn clone_links<A>(&mut self, alloc: &A, rows: &IdMap<A::Id>, targets: &IdMap<GenId<TO, G>>)
+    where
+        A: Allocator<FROM>,
+        A::Id: Copy + Eq + Hash,
+    {
+        let mut links = rows.iter()
+            .filter_map(|(from, to)| Some((alloc.row(&to)?, alloc.row(&from)?)))
+            .map(|(to, from)| {
+                let mut target = self.target(from);
+                target.remap(targets);
+                (to, target)
+            })
+            .collect::<Vec<_>>();
+
+        links.sort_by_key(|(row, _)| row.index);
+        for (row, target) in links {
+            self.insert(row, target);
+        }
+    }
+
+    /// Returns false if the events cannot be used and every row has to be checked instead.
+    pub(crate) fn update_from_events(&mut self, alloc: &DynamicAllocator<TO, G>) -> bool {
+        let referrers = match &mut self.referrers {
+            Some(referrers) if alloc.records_events() => referrers,
+            _ => return false,
+        };
+
+        let (created, killed) = match (alloc.drain_created(&mut referrers.created), alloc.drain_killed(&mut referrers.killed)) {
+            (Ok(created), Ok(killed)) => (created, killed),
+            _ => return false,
+        };
+
+        let ids = &mut self.ids;
+        for target in created.iter().chain(killed) {
+            if let Some(rows) = referrers.rows.get_mut(target.index) {
+                rows.retain(|row| {
+                    let id = &mut ids.0.values[row.index];
+                    let gen = &mut ids.1.values[row.index];
+                    clear_if_invalid(alloc, id, gen);
+                    id.is_some()
+                });
+            }
+        }
+
+        for row in std::mem::take(&mut referrers.inserted) {
+            if let (Some(id), Some(gen)) = (ids.0.values.get_mut(row.index), ids.1.values.get_mut(row.index)) {
+                if let Some(target) = *id {
+                    clear_if_invalid(alloc, id, gen);
+                    if id.is_none() {
+                        referrers.unlink(target, row);
+                    }
+                }
+            }
+        }
+
+        true
+    }
+
+    fn remove_invalid_indices(&mut self, alloc: &DynamicAllocator<TO, G>) {
         self.ids.0.iter_mut()
             .zip(self.ids.1.iter_mut())
-            .for_each(|(id, gen)| {
-                if !alloc.gen.get(*id)
-                    .and_then(|current_gen| gen.map(|gen| *current_gen == gen))
-                    .unwrap_or(false)
-                {
-                    *id = None;
-                    *gen = None;
+            .for_each(|(id, gen)| clear_if_invalid(alloc, id, gen));
+    }
+
+    pub(crate) fn rebuild_referrers(&mut self, alloc: &DynamicAllocator<TO, G>) {
+        if let Some(referrers) = &mut self.referrers {
+            referrers.rows.values.iter_mut().for_each(Vec::clear);
+            referrers.inserted.clear();
+            referrers.created = alloc.created().cursor();
+            referrers.killed = alloc.killed().cursor();
+
+            for (row, target) in self.ids.0.iter().enumerate() {
+                if let Some(target) = target {
+                    referrers.link(*target, Id::new(row));
                 }
-            });
+            }
+        }
+    }
+}
+
+pub(crate) fn clear_if_invalid<TO, G: Generation>(alloc: &DynamicAllocator<TO, G>, id: &mut Option<Id<TO>>, gen: &mut Option<Gen<G>>) {
+    if !id.zip(*gen)
+        .map(|(id, gen)| alloc.is_alive(id, gen))
+        .unwrap_or(false)
+    {
+        *id = None;
+        *gen = None;
+    }
+}
+
+/// One-to-many reverse index, listing the children linked to each parent.
+///
+/// Children may come from a fixed or dynamic allocator; dynamic children are unlinked by `update`
+/// once they are killed.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(bound = ""))]
+pub struct Children<PARENT, CHILD, G: Generation = u32> {
+    children: Comp1<PARENT, Vec<Id<CHILD>>>,
+    links: Comp1<CHILD, Option<Link<PARENT, G>>>,
+    pub version: u64,
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(bound = ""))]
+struct Link<PARENT, G: Generation> {
+    parent: Id<PARENT>,
+    gen: Option<Gen<G>>,
+    position: usize,
+}
+
+impl<PARENT, G: Generation> Clone for Link<PARENT, G> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<PARENT, G: Generation> Copy for Link<PARENT, G> {}
+
+impl<PARENT, CHILD, G: Generation> Default for Children<PARENT, CHILD, G> {
+    fn default() -> Self {
+        Self {
+            children: Default::default(),
+            links: Default::default(),
+            version: Default::default(),
+        }
+    }
+}
+
+impl<PARENT, CHILD, G: Generation> Insert<Id<CHILD>, Id<PARENT>> for Children<PARENT, CHILD, G> {
+    fn insert(&mut self, child: Id<CHILD>, parent: Id<PARENT>) {
+        self.link(child, None, parent);
+    }
+}
+
+impl<PARENT, CHILD, G: Generation> Insert<GenId<CHILD, G>, Id<PARENT>> for Children<PARENT, CHILD, G> {
+    fn insert(&mut self, child: GenId<CHILD, G>, parent: Id<PARENT>) {
+        self.link(child.index, Some(child.gen), parent);
+    }
+}
+
+impl<PARENT, CHILD, G: Generation> Children<PARENT, CHILD, G> {
+    pub fn children(&self, parent: Id<PARENT>) -> &[Id<CHILD>] {
+        self.children.get(parent).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    pub fn parent(&self, child: Id<CHILD>) -> Option<Id<PARENT>> {
+        self.link_of(child).map(|link| link.parent)
+    }
+
+    pub fn unlink(&mut self, child: Id<CHILD>) {
+        let link = match self.links.values.get_mut(child.index).and_then(Option::take) {
+            Some(link) => link,
+            None => return,
+        };
+
+        if let Some(siblings) = self.children.get_mut(link.parent) {
+            siblings.swap_remove(link.position);
+            if let Some(moved) = siblings.get(link.position).copied() {
+                if let Some(Some(moved)) = self.links.values.get_mut(moved.index) {
+                    moved.position = link.position;
+                }
+            }
+        }
+    }
+
+    /// Unlinks children that have been killed since the last update.
+    pub fn update(&mut self, alloc: &DynamicAllocator<CHILD, G>) {
+        if alloc.version != self.version {
+            let dead = self.links.values.iter()
+                .enumerate()
+                .filter_map(|(i, link)| {
+                    let gen = (*link)?.gen?;
+                    let child = Id::new(i);
+                    if alloc.is_alive(child, gen) { None } else { Some(child) }
+                })
+                .collect::<Vec<_>>();
+
+            dead.into_iter().for_each(|child| self.unlink(child));
+            self.version = alloc.version;
+        }
+    }
+
+    /// Packs the child lists into one contiguous buffer for bulk traversal.
+    pub fn csr(&self) -> Csr<PARENT, CHILD> {
+        let mut csr = Csr::default();
+        csr.rebuild(self);
+        csr
+    }
+
+    fn link(&mut self, child: Id<CHILD>, gen: Option<Gen<G>>, parent: Id<PARENT>) {
+        self.unlink(child);
+
+        if self.children.len() <= parent.index {
+            self.children.values.resize_with(parent.index + 1, Vec::new);
+        }
+        if self.links.len() <= child.index {
+            self.links.values.resize(child.index + 1, None);
+        }
+
+        let siblings = &mut self.children.values[parent.index];
+        self.links.values[child.index] = Some(Link { parent, gen, position: siblings.len() });
+        siblings.push(child);
+    }
+
+    fn link_of(&self, child: Id<CHILD>) -> Option<Link<PARENT, G>> {
+        self.links.get(child).copied().flatten()
+    }
+}
+
+/// Compressed sparse row layout of a `Children` index.
+#[derive(Debug, Clone)]
+pub struct Csr<PARENT, CHILD> {
+    offsets: Comp1<PARENT, usize>,
+    children: Vec<Id<CHILD>>,
+}
+
+impl<PARENT, CHILD> Default for Csr<PARENT, CHILD> {
+    fn default() -> Self {
+        Self {
+            offsets: Default::default(),
+            children: Default::default(),
+        }
+    }
+}
+
+impl<PARENT, CHILD> Csr<PARENT, CHILD> {
+    pub fn rebuild<G: Generation>(&mut self, children: &Children<PARENT, CHILD, G>) {
+        self.offsets.values.clear();
+        self.children.clear();
+
+        self.offsets.values.push(0);
+        for siblings in children.children.iter() {
+            self.children.extend_from_slice(siblings);
+            self.offsets.values.push(self.children.len());
+        }
+    }
+
+    pub fn children(&self, parent: Id<PARENT>) -> &[Id<CHILD>] {
+        match (self.offsets.values.get(parent.index), self.offsets.values.get(parent.index + 1)) {
+            (Some(start), Some(end)) => &self.children[*start..*end],
+            _ => &[],
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Id<PARENT>, &[Id<CHILD>])> {
+        self.offsets.values
+            .windows(2)
+            .enumerate()
+            .map(move |(i, range)| (Id::new(i), &self.children[range[0]..range[1]]))
     }
 }
 
@@ -79,4 +425,222 @@ mod tests {
         assert_eq!(dyn_alloc.version, ids.version);
         assert_eq!((&None, &None), ids.ids.get(fixed0).unwrap());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn indexed_update_only_clears_killed_targets() {
+        let mut dyn_alloc = DynamicAllocator::<Type1>::default().with_events();
+        let mut ids = GenIds::<Type2, Type1>::indexed();
+
+        let targets = (0..3).map(|_| dyn_alloc.create()).collect::<Vec<_>>();
+        for (row, target) in [0, 1, 1, 2].iter().enumerate() {
+            ids.insert(Id::<Type2>::new(row), targets[*target]);
+        }
+
+        dyn_alloc.kill(targets[1]);
+        ids.update(&dyn_alloc);
+        assert_eq!(vec![Some(targets[0].index), None, None, Some(targets[2].index)], ids.ids.0.values);
+
+        ids.remove(DenseRow::new(Id::<Type2>::new(0), Id::new(3)));
+        ids.insert(Id::<Type2>::new(1), targets[2]);
+        dyn_alloc.kill(targets[2]);
+        ids.update(&dyn_alloc);
+        assert_eq!(vec![None, None, None], ids.ids.0.values);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn par_update_keeps_the_index_current() {
+        for mut dyn_alloc in vec![DynamicAllocator::<Type1>::default().with_events(), DynamicAllocator::default()] {
+            let mut ids = GenIds::<Type2, Type1>::indexed();
+
+            let targets = (0..3).map(|_| dyn_alloc.create()).collect::<Vec<_>>();
+            for (row, target) in [0, 1, 1, 2].iter().enumerate() {
+                ids.insert(Id::<Type2>::new(row), targets[*target]);
+            }
+
+            dyn_alloc.kill(targets[1]);
+            ids.par_update(&dyn_alloc);
+            assert_eq!(vec![Some(targets[0].index), None, None, Some(targets[2].index)], ids.ids.0.values);
+
+            let referrers = ids.referrers.as_ref().unwrap();
+            assert!(referrers.inserted.is_empty());
+            assert_eq!(Some(&vec![]), referrers.rows.get(targets[1].index));
+
+            ids.insert(Id::<Type2>::new(1), targets[2]);
+            dyn_alloc.kill(targets[2]);
+            ids.update(&dyn_alloc);
+            assert_eq!(vec![Some(targets[0].index), None, None, None], ids.ids.0.values);
+
+            let replacement = dyn_alloc.create();
+            ids.insert(Id::<Type2>::new(2), replacement);
+            dyn_alloc.kill(replacement);
+            ids.par_update(&dyn_alloc);
+            assert_eq!(vec![Some(targets[0].index), None, None, None], ids.ids.0.values);
+            assert_eq!(dyn_alloc.version, ids.version);
+        }
+    }
+
+    #[test]
+    fn clone_links_remaps_targets_in_the_cloned_set() {
+        let mut alloc = DynamicAllocator::<Type1>::default();
+        let mut ids = GenIds::<Type1, Type1>::default();
+
+        let outside = alloc.create();
+        let a = alloc.create();
+        let b = alloc.create();
+        ids.insert(outside, None);
+        ids.insert(a, outside);
+        ids.insert(b, a);
+
+        let mut clones = IdMap::default();
+        clones.insert(a, alloc.create());
+        clones.insert(b, alloc.create());
+        ids.clone_links(&alloc, &clones, &clones);
+
+        let (new_a, new_b) = (clones.get(&a).unwrap(), clones.get(&b).unwrap());
+        assert_eq!(Some(outside), ids.target(alloc.row(&new_a).unwrap()));
+        assert_eq!(Some(new_a), ids.target(alloc.row(&new_b).unwrap()));
+    }
+
+    #[test]
+    fn indexed_update_clears_links_inserted_after_their_target_died() {
+        let mut dyn_alloc = DynamicAllocator::<Type1>::default().with_events();
+        let mut plain = GenIds::<Type2, Type1>::default();
+        let mut indexed = GenIds::<Type2, Type1>::indexed();
+        let row = Id::<Type2>::new(0);
+
+        let t = dyn_alloc.create();
+        let u = dyn_alloc.create();
+        dyn_alloc.kill(t);
+        plain.update(&dyn_alloc);
+        indexed.update(&dyn_alloc);
+
+        plain.insert(row, t);
+        indexed.insert(row, t);
+        dyn_alloc.kill(u);
+        plain.update(&dyn_alloc);
+        indexed.update(&dyn_alloc);
+
+        assert_eq!(None, plain.target(row));
+        assert_eq!(plain.target(row), indexed.target(row));
+        assert_eq!(plain.ids.0.values, indexed.ids.0.values);
+    }
+
+    #[test]
+    fn indexed_update_clears_links_to_reused_slots() {
+        let mut dyn_alloc = DynamicAllocator::<Type1>::default().with_events();
+        let mut ids = GenIds::<Type2, Type1>::indexed();
+
+        let dead = dyn_alloc.create();
+        dyn_alloc.kill(dead);
+        ids.insert(Id::<Type2>::new(0), dead);
+
+        let reused = dyn_alloc.create();
+        assert_eq!(dead.index, reused.index);
+        ids.update(&dyn_alloc);
+        assert_eq!((&None, &None), ids.ids.get(Id::<Type2>::new(0)).unwrap());
+    }
+
+    #[test]
+    fn indexed_update_falls_back_after_lag() {
+        let mut dyn_alloc = DynamicAllocator::<Type1>::default().with_events();
+        let mut ids = GenIds::<Type2, Type1>::indexed();
+
+        let target = dyn_alloc.create();
+        ids.insert(Id::<Type2>::new(0), target);
+        dyn_alloc.kill(target);
+        dyn_alloc.clear_events();
+
+        ids.update(&dyn_alloc);
+        assert_eq!((&None, &None), ids.ids.get(Id::<Type2>::new(0)).unwrap());
+
+        let target = dyn_alloc.create();
+        ids.insert(Id::<Type2>::new(0), target);
+        dyn_alloc.kill(target);
+        ids.update(&dyn_alloc);
+        assert_eq!((&None, &None), ids.ids.get(Id::<Type2>::new(0)).unwrap());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let mut dyn_alloc = DynamicAllocator::<Type1>::default();
+        let mut fixed_alloc = FixedAllocator::<Type2>::default();
+        let mut ids = GenIds::<Type2, Type1>::default();
+        let mut values = Comp2::<Type2, u32, f64>::default();
+
+        let dyn0 = dyn_alloc.create();
+        let fixed0 = fixed_alloc.create();
+        ids.insert(fixed0, dyn0);
+        values.insert(fixed0, (3, 0.5));
+
+        let json = serde_json::to_string(&(&dyn_alloc, &fixed_alloc, &ids, &values)).unwrap();
+        type State = (DynamicAllocator<Type1>, FixedAllocator<Type2>, GenIds<Type2, Type1>, Comp2<Type2, u32, f64>);
+        let (mut dyn_alloc, mut fixed_alloc, mut ids, values): State = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(Some((&3, &0.5)), values.get(fixed0));
+        assert_eq!(1, fixed_alloc.create().index);
+
+        dyn_alloc.kill(dyn0);
+        ids.update(&dyn_alloc);
+        assert_eq!((&None, &None), ids.ids.get(fixed0).unwrap());
+    }
+
+    #[test]
+    fn children_fixed() {
+        let mut bodies = FixedAllocator::<Type2>::default();
+        let mut children = Children::<Type2, Type2>::default();
+
+        let sun = bodies.create();
+        let earth = bodies.create();
+        let moon = bodies.create();
+        let mars = bodies.create();
+
+        children.insert(earth, sun);
+        children.insert(mars, sun);
+        children.insert(moon, earth);
+
+        assert_eq!(&[earth, mars], children.children(sun));
+        assert_eq!(&[moon], children.children(earth));
+        assert_eq!(Some(sun), children.parent(mars));
+        assert_eq!(None, children.parent(sun));
+
+        children.unlink(earth);
+        assert_eq!(&[mars], children.children(sun));
+
+        children.insert(moon, sun);
+        assert_eq!(&[mars, moon], children.children(sun));
+        assert!(children.children(earth).is_empty());
+
+        let csr = children.csr();
+        assert_eq!(&[mars, moon], csr.children(sun));
+        assert!(csr.children(moon).is_empty());
+        assert_eq!(vec![(sun, &[mars, moon][..]), (earth, &[][..])], csr.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn children_unlinked_on_death() {
+        let mut colonies = DynamicAllocator::<Type1>::default();
+        let mut bodies = FixedAllocator::<Type2>::default();
+        let mut children = Children::<Type2, Type1>::default();
+
+        let earth = bodies.create();
+        let a = colonies.create();
+        let b = colonies.create();
+        let c = colonies.create();
+        children.insert(a, earth);
+        children.insert(b, earth);
+        children.insert(c, earth);
+
+        colonies.kill(a);
+        children.update(&colonies);
+
+        assert_eq!(&[c.index, b.index], children.children(earth));
+        assert_eq!(None, children.parent(a.index));
+
+        let d = colonies.create();
+        assert_eq!(a.index, d.index);
+        children.update(&colonies);
+        assert_eq!(&[c.index, b.index], children.children(earth));
+    }
+}
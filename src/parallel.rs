@@ -0,0 +1,159 @@
+use crate::allocators::{DynamicAllocator, Generation, Id, Valid};
+use crate::components::{Comp1, Comp2};
+use crate::links::{clear_if_invalid, GenIds};
+use crate::Get1;
+use rayon::prelude::*;
+
+impl<ID, T> Comp1<ID, T> {
+    pub fn par_iter(&self) -> impl IndexedParallelIterator<Item = &T>
+    where
+        T: Sync,
+    {
+        self.values.par_iter()
+    }
+
+    pub fn par_iter_mut(&mut self) -> impl IndexedParallelIterator<Item = &mut T>
+    where
+        T: Send,
+    {
+        self.values.par_iter_mut()
+    }
+}
+
+impl<ID1, T: Copy + Send + Sync> Comp1<ID1, T> {
+    pub fn par_get_from<ID2>(&mut self, rhs: &Comp1<ID2, T>, ids: &Comp1<ID1, Id<ID2>>)
+    where
+        ID2: Sync,
+    {
+        self.par_iter_mut()
+            .zip(ids.par_iter())
+            .for_each(|(value, id)| {
+                if let Some(v) = rhs.get(id) {
+                    *value = *v;
+                }
+            });
+    }
+
+    pub fn par_get_from_or<ID2>(&mut self, rhs: &Comp1<ID2, T>, ids: &Valid<ID1, ID2>, fallback: T)
+    where
+        ID1: Sync,
+        ID2: Sync,
+    {
+        self.par_iter_mut()
+            .zip(ids.ids.par_iter())
+            .for_each(|(v, id)|
+                *v = ids.resolve(*id)
+                    .and_then(|id| rhs.get(id))
+                    .copied()
+                    .unwrap_or(fallback)
+            );
+    }
+}
+
+impl<ID, T1, T2> Comp2<ID, T1, T2> {
+    pub fn par_iter(&self) -> impl IndexedParallelIterator<Item = (&T1, &T2)>
+    where
+        T1: Sync,
+        T2: Sync,
+    {
+        self.0.par_iter().zip(self.1.par_iter())
+    }
+
+    pub fn par_iter_mut(&mut self) -> impl IndexedParallelIterator<Item = (&mut T1, &mut T2)>
+    where
+        T1: Send,
+        T2: Send,
+    {
+        self.0.par_iter_mut().zip(self.1.par_iter_mut())
+    }
+}
+
+impl<ID, T1: Copy + Send + Sync, T2: Copy + Send + Sync> Comp2<ID, T1, T2> {
+    pub fn par_get_from<ID2>(&mut self, rhs: &Comp2<ID2, T1, T2>, ids: &Comp1<ID, Id<ID2>>)
+    where
+        ID2: Sync,
+    {
+        self.0.par_get_from(&rhs.0, ids);
+        self.1.par_get_from(&rhs.1, ids);
+    }
+
+    pub fn par_get_from_or<ID2>(&mut self, rhs: &Comp2<ID2, T1, T2>, ids: &Valid<ID, ID2>, fallback: (T1, T2))
+    where
+        ID: Sync,
+        ID2: Sync,
+    {
+        self.0.par_get_from_or(&rhs.0, ids, fallback.0);
+        self.1.par_get_from_or(&rhs.1, ids, fallback.1);
+    }
+}
+
+impl<FROM, TO: Send + Sync, G: Generation> GenIds<FROM, TO, G> {
+    /// Like `update`, but checks every row in parallel when the allocator's events cannot be used.
+    pub fn par_update(&mut self, alloc: &DynamicAllocator<TO, G>) {
+        if alloc.version != self.version {
+            if !self.update_from_events(alloc) {
+                self.ids.par_iter_mut()
+                    .for_each(|(id, gen)| clear_if_invalid(alloc, id, gen));
+                self.rebuild_referrers(alloc);
+            }
+            self.version = alloc.version;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::allocators::FixedAllocator;
+    use crate::Insert;
+
+    #[derive(Debug, Default)] struct Type1;
+    #[derive(Debug, Default)] struct Type2;
+
+    #[test]
+    fn par_zip_matches_sequential() {
+        let mut a = FixedAllocator::<()>::default();
+        let mut t1 = Comp1::<(), u32>::default();
+        let mut t2 = Comp2::<(), u32, u32>::default();
+
+        for i in 0..1000 {
+            let id = a.create();
+            t1.insert(id, i);
+            t2.insert(id, (i, 2));
+        }
+
+        t1.par_iter_mut()
+            .zip(t2.par_iter())
+            .for_each(|(a, (b, c))| *a += *b * *c);
+
+        assert!(t1.iter().enumerate().all(|(i, v)| *v == 3 * i as u32));
+    }
+
+    #[test]
+    fn par_get_from_or() {
+        let mut dyn_alloc = DynamicAllocator::<Type1>::default();
+        let mut fixed_alloc = FixedAllocator::<Type2>::default();
+        let mut from = Comp2::<Type1, u32, u32>::default();
+        let mut to = Comp2::<Type2, u32, u32>::default();
+        let mut links = GenIds::<Type2, Type1>::default();
+
+        let ids = (0..100).map(|i| {
+            let target = dyn_alloc.create();
+            from.insert(target, (i, i + 1));
+
+            let id = fixed_alloc.create();
+            to.insert(id, (0, 0));
+            links.insert(id, target);
+            target
+        }).collect::<Vec<_>>();
+
+        dyn_alloc.kill(ids[10]);
+        links.par_update(&dyn_alloc);
+        assert_eq!(dyn_alloc.version, links.version);
+
+        to.par_get_from_or(&from, &dyn_alloc.validate(&mut links), (7, 7));
+
+        assert_eq!((&7, &7), to.iter().nth(10).unwrap());
+        assert_eq!((&11, &12), to.iter().nth(11).unwrap());
+    }
+}
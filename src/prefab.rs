@@ -0,0 +1,283 @@
+use crate::allocators::{Allocator, GenId, Generation, Id};
+use crate::links::GenIds;
+use crate::Table;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// The new id of each entity in a cloned set, keyed by the id it was cloned from.
+#[derive(Debug, Clone)]
+pub struct IdMap<I> {
+    ids: HashMap<I, I>,
+}
+
+impl<I> Default for IdMap<I> {
+    fn default() -> Self {
+        Self {
+            ids: Default::default(),
+        }
+    }
+}
+
+impl<I: Copy + Eq + Hash> IdMap<I> {
+    pub fn insert(&mut self, from: I, to: I) {
+        self.ids.insert(from, to);
+    }
+
+    pub fn get(&self, from: &I) -> Option<I> {
+        self.ids.get(from).copied()
+    }
+
+    /// `(from, to)` pairs in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (I, I)> + '_ {
+        self.ids.iter().map(|(from, to)| (*from, *to))
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+}
+
+/// Rewrites links that point into a cloned set so they point at the clones instead.
+///
+/// Generated for `#[derive(Table)]` rows, where fields marked `#[table(link)]` are remapped.
+pub trait Remap<I> {
+    fn remap(&mut self, map: &IdMap<I>);
+}
+
+impl<T> Remap<Id<T>> for Id<T> {
+    fn remap(&mut self, map: &IdMap<Id<T>>) {
+        if let Some(id) = map.get(self) {
+            *self = id;
+        }
+    }
+}
+
+impl<T, G: Generation> Remap<GenId<T, G>> for GenId<T, G> {
+    fn remap(&mut self, map: &IdMap<GenId<T, G>>) {
+        if let Some(id) = map.get(self) {
+            *self = id;
+        }
+    }
+}
+
+impl<I, R: Remap<I>> Remap<I> for Option<R> {
+    fn remap(&mut self, map: &IdMap<I>) {
+        if let Some(value) = self {
+            value.remap(map);
+        }
+    }
+}
+
+impl<I, R: Remap<I>> Remap<I> for Vec<R> {
+    fn remap(&mut self, map: &IdMap<I>) {
+        self.iter_mut().for_each(|value| value.remap(map));
+    }
+}
+
+/// The rows of a set of entities, which can be instantiated any number of times.
+///
+/// Links between entities of the set are remapped to the new entities on each instantiation,
+/// while links to entities outside the set are kept as they are.
+#[derive(Debug, Clone)]
+pub struct Prefab<T: Table, I> {
+    ids: Vec<I>,
+    rows: Vec<T::Row>,
+}
+
+impl<T: Table, I: Copy + Eq + Hash> Prefab<T, I> {
+    /// Copies the rows of `ids`. Returns `None` if any of them is dead or missing a column.
+    pub fn capture<A>(table: &T, alloc: &A, ids: impl IntoIterator<Item = I>) -> Option<Self>
+    where
+        A: Allocator<T, Id = I>,
+    {
+        let ids = ids.into_iter().collect::<Vec<_>>();
+        let rows = ids.iter()
+            .map(|id| table.clone_row(alloc.row(id)?))
+            .collect::<Option<Vec<_>>>()?;
+
+        Some(Self { ids, rows })
+    }
+
+    /// The ids the rows were captured from.
+    pub fn ids(&self) -> &[I] {
+        &self.ids
+    }
+
+    pub fn rows(&self) -> &[T::Row] {
+        &self.rows
+    }
+
+    /// Creates a new entity for every captured row, returning the new id of each captured id.
+    pub fn instantiate<A>(&self, table: &mut T, alloc: &mut A) -> IdMap<I>
+    where
+        A: Allocator<T, Id = I>,
+        T::Row: Clone + Remap<I>,
+    {
+        let mut map = IdMap::default();
+        let created = self.ids.iter()
+            .map(|id| {
+                let new = alloc.create();
+                map.insert(*id, new);
+                new
+            })
+            .collect::<Vec<_>>();
+
+        for (new, row) in created.iter().zip(&self.rows) {
+            let mut row = row.clone();
+            row.remap(&map);
+            let index = alloc.row(new).expect("newly created id is alive");
+            table.insert_row(index, row);
+        }
+
+        map
+    }
+}
+
+impl<T: Table, G: Generation> Prefab<T, GenId<T, G>> {
+    /// Like `instantiate`, and also copies the links that the captured entities hold in `links`,
+    /// which are tables of links between entities of `T`. Links read from the captured entities
+    /// as they are now, so those entities must still be alive.
+    ///
+    /// Links to entities of another table are copied unchanged with `GenIds::clone_links`, passing
+    /// the returned map and an empty target map.
+    pub fn instantiate_with_links<A>(&self, table: &mut T, alloc: &mut A, links: &mut [&mut GenIds<T, T, G>]) -> IdMap<GenId<T, G>>
+    where
+        A: Allocator<T, Id = GenId<T, G>>,
+        T::Row: Clone + Remap<GenId<T, G>>,
+    {
+        let map = self.instantiate(table, alloc);
+        for link in links {
+            link.clone_links(alloc, &map, &map);
+        }
+        map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::allocators::{DynamicAllocator, FixedAllocator, Indexes};
+    use crate::components::Comp1;
+    use crate::{Get1, Insert};
+
+    #[derive(Debug, Default)]
+    struct Colony {
+        name: Comp1<Self, &'static str>,
+        parent: Comp1<Self, Option<GenId<Self>>>,
+    }
+
+    #[derive(Debug, Clone)]
+    struct ColonyRow {
+        name: &'static str,
+        parent: Option<GenId<Colony>>,
+    }
+
+    impl Table for Colony {
+        type Row = ColonyRow;
+
+        fn insert_row<I: Indexes<Self>>(&mut self, id: I, row: ColonyRow) {
+            self.name.insert(id, row.name);
+            self.parent.insert(id, row.parent);
+        }
+
+        fn clone_row(&self, id: Id<Self>) -> Option<ColonyRow> {
+            Some(ColonyRow {
+                name: *self.name.get(id)?,
+                parent: *self.parent.get(id)?,
+            })
+        }
+
+        fn column_lengths(&self) -> Vec<(&'static str, usize)> {
+            vec![("name", self.name.len()), ("parent", self.parent.len())]
+        }
+    }
+
+    impl Remap<GenId<Colony>> for ColonyRow {
+        fn remap(&mut self, map: &IdMap<GenId<Colony>>) {
+            self.parent.remap(map);
+        }
+    }
+
+    impl Remap<Id<Colony>> for ColonyRow {
+        fn remap(&mut self, _: &IdMap<Id<Colony>>) {}
+    }
+
+    fn create(table: &mut Colony, alloc: &mut DynamicAllocator<Colony>, name: &'static str, parent: Option<GenId<Colony>>) -> GenId<Colony> {
+        let id = alloc.create();
+        table.insert_row(alloc.row(&id).unwrap(), ColonyRow { name, parent });
+        id
+    }
+
+    #[test]
+    fn links_within_the_set_are_remapped() {
+        let mut alloc = DynamicAllocator::default();
+        let mut table = Colony::default();
+
+        let capital = create(&mut table, &mut alloc, "capital", None);
+        let city = create(&mut table, &mut alloc, "city", Some(capital));
+        let town = create(&mut table, &mut alloc, "town", Some(city));
+
+        let prefab = Prefab::capture(&table, &alloc, vec![city, town]).unwrap();
+        let first = prefab.instantiate(&mut table, &mut alloc);
+        let second = prefab.instantiate(&mut table, &mut alloc);
+
+        for map in &[first, second] {
+            let (new_city, new_town) = (map.get(&city).unwrap(), map.get(&town).unwrap());
+            assert_ne!(city, new_city);
+            assert_eq!(Some(&"town"), table.name.get(alloc.row(&new_town).unwrap()));
+            assert_eq!(Some(&Some(new_city)), table.parent.get(alloc.row(&new_town).unwrap()));
+            assert_eq!(Some(&Some(capital)), table.parent.get(alloc.row(&new_city).unwrap()));
+        }
+        assert_eq!(Ok(()), table.check_lengths(&alloc));
+    }
+
+    #[test]
+    fn link_tables_are_copied_and_remapped() {
+        let mut alloc = DynamicAllocator::default();
+        let mut table = Colony::default();
+        let mut allies = GenIds::<Colony, Colony>::default();
+
+        let capital = create(&mut table, &mut alloc, "capital", None);
+        let city = create(&mut table, &mut alloc, "city", None);
+        let town = create(&mut table, &mut alloc, "town", None);
+        allies.insert(alloc.row(&capital).unwrap(), None);
+        allies.insert(alloc.row(&city).unwrap(), Some(capital));
+        allies.insert(alloc.row(&town).unwrap(), Some(city));
+
+        let prefab = Prefab::capture(&table, &alloc, vec![city, town]).unwrap();
+        let map = prefab.instantiate_with_links(&mut table, &mut alloc, &mut [&mut allies]);
+
+        let (new_city, new_town) = (map.get(&city).unwrap(), map.get(&town).unwrap());
+        assert_eq!(Some(capital), allies.target(alloc.row(&new_city).unwrap()));
+        assert_eq!(Some(new_city), allies.target(alloc.row(&new_town).unwrap()));
+        assert_eq!(Some(city), allies.target(alloc.row(&town).unwrap()));
+        assert_eq!(alloc.rows(), allies.ids.len());
+    }
+
+    #[test]
+    fn clone_entity_copies_a_row() {
+        let mut alloc = FixedAllocator::default();
+        let mut table = Colony::default();
+
+        let id = alloc.create();
+        table.insert_row(id, ColonyRow { name: "outpost", parent: None });
+
+        let clone = table.clone_entity(&mut alloc, id).unwrap();
+        assert_ne!(id, clone);
+        assert_eq!(Some(&"outpost"), table.name.get(clone));
+    }
+
+    #[test]
+    fn capture_fails_for_dead_ids() {
+        let mut alloc = DynamicAllocator::default();
+        let mut table = Colony::default();
+
+        let id = create(&mut table, &mut alloc, "ruins", None);
+        alloc.kill(id);
+        assert!(Prefab::capture(&table, &alloc, Some(id)).is_none());
+    }
+}
@@ -1,3 +1,9 @@
 pub use crate::allocators::*;
 pub use crate::components::*;
-pub use crate::{Insert, Get1, Get2};
\ No newline at end of file
+pub use crate::query::{Cursor, LengthMismatch, Query, QueryColumns, QueryFilter};
+pub use crate::commands::Commands;
+pub use crate::prefab::{IdMap, Prefab, Remap};
+pub use crate::query;
+pub use crate::schedule::{Schedule, Stage, System};
+pub use crate::world::{Entity, EntityId, Res, ResMut, World, WorldError};
+pub use crate::{Insert, Get1, Get2, Get3, Get4, Get5, Get6, Get7, Get8, Remove, Table, ColumnLengthError};
\ No newline at end of file
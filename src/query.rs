@@ -0,0 +1,491 @@
+use crate::allocators::Allocator;
+use crate::bitset::BitSet;
+use crate::components::{Comp1, Comp2, SparseComp};
+use std::fmt;
+use std::marker::PhantomData;
+
+/// Builds a `Query` from a list of column references, e.g. `query!(&mut position, &period, &radius)`.
+#[macro_export]
+macro_rules! query {
+    ($($column:expr),+ $(,)?) => {
+        $crate::query::Query::new(($($column,)+))
+    };
+}
+
+/// Columns that can be visited together, row by row.
+///
+/// Implemented for shared and mutable references to `Comp1` and `Comp2`, and for tuples of up
+/// to eight columns indexed by the same entity type.
+pub trait QueryColumns<'a> {
+    type Id;
+    type Cursor: Cursor<'a>;
+
+    fn len(&self) -> usize;
+    fn cursor(self) -> Self::Cursor;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The shared length of the columns, or the first column that disagrees with the first one.
+    fn check_len(&self) -> Result<usize, LengthMismatch> {
+        Ok(self.len())
+    }
+
+    fn type_name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
+}
+
+/// Two columns that were expected to be visited in lockstep have different lengths.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct LengthMismatch {
+    pub column: usize,
+    pub column_type: &'static str,
+    pub len: usize,
+    pub expected_type: &'static str,
+    pub expected_len: usize,
+}
+
+impl fmt::Display for LengthMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "query column {} ({}) has length {} but column 0 ({}) has length {}",
+            self.column, self.column_type, self.len, self.expected_type, self.expected_len
+        )
+    }
+}
+
+impl std::error::Error for LengthMismatch {}
+
+/// Hands out the values of one or more columns, one row at a time.
+pub trait Cursor<'a> {
+    type Item;
+
+    /// Panics if `index` is not greater than the previously fetched index.
+    fn fetch(&mut self, index: usize) -> Self::Item;
+}
+
+impl<'a, T> Cursor<'a> for &'a [T] {
+    type Item = &'a T;
+
+    fn fetch(&mut self, index: usize) -> &'a T {
+        let values: &'a [T] = self;
+        &values[index]
+    }
+}
+
+/// Mutable cursor that splits each fetched value off the front of the column.
+#[derive(Debug)]
+pub struct CursorMut<'a, T> {
+    rest: &'a mut [T],
+    offset: usize,
+}
+
+impl<'a, T> CursorMut<'a, T> {
+    fn new(values: &'a mut [T]) -> Self {
+        Self {
+            rest: values,
+            offset: 0,
+        }
+    }
+}
+
+impl<'a, T> Cursor<'a> for CursorMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn fetch(&mut self, index: usize) -> &'a mut T {
+        assert!(index >= self.offset, "query rows must be visited in increasing order");
+        let rest = std::mem::take(&mut self.rest);
+        let (value, rest) = rest[index - self.offset..]
+            .split_first_mut()
+            .expect("query row out of bounds");
+        self.rest = rest;
+        self.offset = index + 1;
+        value
+    }
+}
+
+impl<'a, ID, T> QueryColumns<'a> for &'a Comp1<ID, T> {
+    type Id = ID;
+    type Cursor = &'a [T];
+
+    fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    fn cursor(self) -> &'a [T] {
+        &self.values
+    }
+}
+
+impl<'a, ID, T> QueryColumns<'a> for &'a mut Comp1<ID, T> {
+    type Id = ID;
+    type Cursor = CursorMut<'a, T>;
+
+    fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    fn cursor(self) -> CursorMut<'a, T> {
+        CursorMut::new(&mut self.values)
+    }
+}
+
+impl<'a, ID, T1, T2> QueryColumns<'a> for &'a Comp2<ID, T1, T2> {
+    type Id = ID;
+    type Cursor = (&'a [T1], &'a [T2]);
+
+    fn len(&self) -> usize {
+        Comp2::len(self)
+    }
+
+    fn cursor(self) -> (&'a [T1], &'a [T2]) {
+        (&self.0.values, &self.1.values)
+    }
+}
+
+impl<'a, ID, T1, T2> QueryColumns<'a> for &'a mut Comp2<ID, T1, T2> {
+    type Id = ID;
+    type Cursor = (CursorMut<'a, T1>, CursorMut<'a, T2>);
+
+    fn len(&self) -> usize {
+        Comp2::len(self)
+    }
+
+    fn cursor(self) -> (CursorMut<'a, T1>, CursorMut<'a, T2>) {
+        (CursorMut::new(&mut self.0.values), CursorMut::new(&mut self.1.values))
+    }
+}
+
+macro_rules! query_tuple {
+    ($first:ident $(, $t:ident, $i:tt)*) => {
+        impl<'a, $first: Cursor<'a>, $($t: Cursor<'a>),*> Cursor<'a> for ($first, $($t,)*) {
+            type Item = ($first::Item, $($t::Item,)*);
+
+            fn fetch(&mut self, index: usize) -> Self::Item {
+                (self.0.fetch(index), $(self.$i.fetch(index),)*)
+            }
+        }
+
+        impl<'a, $first: QueryColumns<'a>, $($t: QueryColumns<'a, Id = $first::Id>),*> QueryColumns<'a> for ($first, $($t,)*) {
+            type Id = $first::Id;
+            type Cursor = ($first::Cursor, $($t::Cursor,)*);
+
+            fn len(&self) -> usize {
+                self.0.len()
+            }
+
+            fn check_len(&self) -> Result<usize, LengthMismatch> {
+                let len = self.0.len();
+                $(
+                    if self.$i.len() != len {
+                        return Err(LengthMismatch {
+                            column: $i,
+                            column_type: self.$i.type_name(),
+                            len: self.$i.len(),
+                            expected_type: self.0.type_name(),
+                            expected_len: len,
+                        });
+                    }
+                )*
+                Ok(len)
+            }
+
+            fn cursor(self) -> Self::Cursor {
+                (self.0.cursor(), $(self.$i.cursor(),)*)
+            }
+        }
+    };
+}
+
+query_tuple!(A);
+query_tuple!(A, B, 1);
+query_tuple!(A, B, 1, C, 2);
+query_tuple!(A, B, 1, C, 2, D, 3);
+query_tuple!(A, B, 1, C, 2, D, 3, E, 4);
+query_tuple!(A, B, 1, C, 2, D, 3, E, 4, F, 5);
+query_tuple!(A, B, 1, C, 2, D, 3, E, 4, F, 5, G, 6);
+query_tuple!(A, B, 1, C, 2, D, 3, E, 4, F, 5, G, 6, H, 7);
+
+/// Restricts which rows a query visits.
+pub trait QueryFilter<ID> {
+    /// Calls `f` with every matching row below `len`, in increasing order.
+    fn for_each_index(&self, len: usize, f: impl FnMut(usize));
+    fn matches(&self, index: usize) -> bool;
+}
+
+impl<ID> QueryFilter<ID> for () {
+    fn for_each_index(&self, len: usize, f: impl FnMut(usize)) {
+        (0..len).for_each(f);
+    }
+
+    fn matches(&self, _: usize) -> bool {
+        true
+    }
+}
+
+impl<ID> QueryFilter<ID> for &BitSet {
+    fn for_each_index(&self, len: usize, f: impl FnMut(usize)) {
+        self.iter().take_while(|index| *index < len).for_each(f);
+    }
+
+    fn matches(&self, index: usize) -> bool {
+        self.get(index)
+    }
+}
+
+impl<ID, T> QueryFilter<ID> for &SparseComp<ID, T> {
+    fn for_each_index(&self, len: usize, f: impl FnMut(usize)) {
+        let mut indices = self.ids()
+            .iter()
+            .map(|id| id.index)
+            .filter(|index| *index < len)
+            .collect::<Vec<_>>();
+        indices.sort_unstable();
+        indices.into_iter().for_each(f);
+    }
+
+    fn matches(&self, index: usize) -> bool {
+        self.contains(crate::allocators::Id::<ID>::new(index))
+    }
+}
+
+/// Filter that keeps the rows an allocator reports as alive.
+#[derive(Debug)]
+pub struct Alive<'a, A>(pub &'a A);
+
+impl<ID, A: Allocator<ID>> QueryFilter<ID> for Alive<'_, A> {
+    fn for_each_index(&self, len: usize, f: impl FnMut(usize)) {
+        (0..len).filter(|index| self.0.is_alive_index(*index)).for_each(f);
+    }
+
+    fn matches(&self, index: usize) -> bool {
+        self.0.is_alive_index(index)
+    }
+}
+
+impl<ID, F1: QueryFilter<ID>, F2: QueryFilter<ID>> QueryFilter<ID> for (F1, F2) {
+    fn for_each_index(&self, len: usize, mut f: impl FnMut(usize)) {
+        self.0.for_each_index(len, |index| {
+            if self.1.matches(index) {
+                f(index);
+            }
+        });
+    }
+
+    fn matches(&self, index: usize) -> bool {
+        self.0.matches(index) && self.1.matches(index)
+    }
+}
+
+/// Visits the same row of several columns at once, without nested `zip` calls.
+///
+/// The most recently added filter drives the iteration, so add sparse filters last.
+pub struct Query<'a, Q: QueryColumns<'a>, F = ()> {
+    cursor: Q::Cursor,
+    filter: F,
+    len: usize,
+}
+
+impl<'a, Q: QueryColumns<'a>> Query<'a, Q> {
+    /// Panics if the columns have different lengths.
+    pub fn new(columns: Q) -> Self {
+        Self::try_new(columns).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn try_new(columns: Q) -> Result<Self, LengthMismatch> {
+        Ok(Self {
+            len: columns.check_len()?,
+            cursor: columns.cursor(),
+            filter: (),
+        })
+    }
+}
+
+/// Iterates over several columns in lockstep, like `Iterator::zip` but never truncating silently.
+pub struct Zip<'a, Q: QueryColumns<'a>> {
+    cursor: Q::Cursor,
+    next: usize,
+    len: usize,
+    marker: PhantomData<&'a ()>,
+}
+
+/// Zips the columns, panicking with their type names if their lengths differ.
+pub fn zip<'a, Q: QueryColumns<'a>>(columns: Q) -> Zip<'a, Q> {
+    try_zip(columns).unwrap_or_else(|error| panic!("{}", error))
+}
+
+pub fn try_zip<'a, Q: QueryColumns<'a>>(columns: Q) -> Result<Zip<'a, Q>, LengthMismatch> {
+    Ok(Zip {
+        len: columns.check_len()?,
+        cursor: columns.cursor(),
+        next: 0,
+        marker: PhantomData,
+    })
+}
+
+impl<'a, Q: QueryColumns<'a>> Iterator for Zip<'a, Q> {
+    type Item = <Q::Cursor as Cursor<'a>>::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next < self.len {
+            let item = self.cursor.fetch(self.next);
+            self.next += 1;
+            Some(item)
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.len - self.next;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<'a, Q: QueryColumns<'a>> ExactSizeIterator for Zip<'a, Q> {}
+
+impl<'a, Q: QueryColumns<'a>, F: QueryFilter<Q::Id>> Query<'a, Q, F> {
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn filter<F2: QueryFilter<Q::Id>>(self, filter: F2) -> Query<'a, Q, (F2, F)> {
+        Query {
+            cursor: self.cursor,
+            filter: (filter, self.filter),
+            len: self.len,
+        }
+    }
+
+    /// Only visits rows whose bit is set in `mask`.
+    pub fn mask(self, mask: &BitSet) -> Query<'a, Q, (&BitSet, F)> {
+        self.filter(mask)
+    }
+
+    /// Only visits rows that are alive in `alloc`.
+    pub fn alive<A: Allocator<Q::Id>>(self, alloc: &A) -> Query<'a, Q, (Alive<'_, A>, F)> {
+        self.filter(Alive(alloc))
+    }
+
+    /// Only visits rows that have a value in `sparse`, walking the sparse column's entries.
+    pub fn with<T>(self, sparse: &SparseComp<Q::Id, T>) -> Query<'a, Q, (&SparseComp<Q::Id, T>, F)> {
+        self.filter(sparse)
+    }
+
+    pub fn for_each(self, mut f: impl FnMut(<Q::Cursor as Cursor<'a>>::Item)) {
+        let mut cursor = self.cursor;
+        self.filter.for_each_index(self.len, |index| f(cursor.fetch(index)));
+    }
+
+    /// Like `for_each`, also passing the id of each row's entity. Rows without a live entity are skipped.
+    pub fn for_each_with_id<A: Allocator<Q::Id>>(self, alloc: &A, mut f: impl FnMut(A::Id, <Q::Cursor as Cursor<'a>>::Item)) {
+        let mut cursor = self.cursor;
+        self.filter.for_each_index(self.len, |index| {
+            if let Some(id) = alloc.live_id(index) {
+                f(id, cursor.fetch(index));
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::allocators::{DynamicAllocator, FixedAllocator};
+    use crate::Insert;
+
+    #[derive(Debug, Default)]
+    struct Type1;
+
+    #[test]
+    fn for_each_flattens_columns() {
+        let mut a = FixedAllocator::<Type1>::default();
+        let mut position = Comp2::<Type1, f32, f32>::default();
+        let mut speed = Comp1::<Type1, f32>::default();
+        let mut scale = Comp1::<Type1, f32>::default();
+
+        for i in 0..3 {
+            let id = a.create();
+            position.insert(id, (0.0, i as f32));
+            speed.insert(id, i as f32);
+            scale.insert(id, 2.0);
+        }
+
+        query!(&mut position, &speed, &scale).for_each(|((x, y), speed, scale)| {
+            *x += speed * scale;
+            *y += 1.0;
+        });
+
+        assert_eq!(vec![0.0, 2.0, 4.0], position.0.values);
+        assert_eq!(vec![1.0, 2.0, 3.0], position.1.values);
+    }
+
+    #[test]
+    #[should_panic(expected = "query column 1 (&simd_ecs::components::comp1::Comp1<")]
+    fn mismatched_lengths_panic() {
+        let mut a = Comp1::<Type1, u32>::default();
+        let b = Comp1::<Type1, u32>::default();
+        a.insert(crate::allocators::Id::new(0), 1);
+
+        Query::new((&mut a, &b));
+    }
+
+    #[test]
+    fn try_zip_names_the_short_column() {
+        let mut a = Comp1::<Type1, u32>::default();
+        let mut b = Comp2::<Type1, u32, u32>::default();
+        a.insert(crate::allocators::Id::new(0), 1);
+
+        let error = try_zip((&a, &b)).err().unwrap();
+        assert_eq!(1, error.column);
+        assert_eq!(0, error.len);
+        assert_eq!(1, error.expected_len);
+        assert!(error.column_type.contains("Comp2"));
+        assert!(error.to_string().starts_with("query column 1 (&simd_ecs::components::comp2::Comp2<"));
+
+        b.insert(crate::allocators::Id::new(0), (2, 3));
+        for (a, (b, c)) in zip((&mut a, &b)) {
+            *a += *b + *c;
+        }
+        assert_eq!(vec![6], a.values);
+    }
+
+    #[test]
+    fn filters() {
+        let mut a = DynamicAllocator::<Type1>::default();
+        let mut values = Comp1::<Type1, u32>::default();
+        let mut tagged = SparseComp::<Type1, ()>::default();
+
+        let ids = (0..4).map(|i| {
+            let id = a.create();
+            values.insert(id, i);
+            id
+        }).collect::<Vec<_>>();
+        a.kill(ids[1]);
+        tagged.insert(ids[3], ());
+        tagged.insert(ids[1], ());
+
+        let mut seen = vec![];
+        query!(&values).alive(&a).for_each(|(v,)| seen.push(*v));
+        assert_eq!(vec![0, 2, 3], seen);
+
+        let mut seen = vec![];
+        query!(&values).mask(a.living()).with(&tagged).for_each(|(v,)| seen.push(*v));
+        assert_eq!(vec![3], seen);
+
+        let mut seen = vec![];
+        query!(&mut values).for_each_with_id(&a, |id, (v,)| {
+            *v += 10;
+            seen.push(id);
+        });
+        assert_eq!(vec![ids[0], ids[2], ids[3]], seen);
+        assert_eq!(vec![10, 1, 12, 13], values.values);
+    }
+}
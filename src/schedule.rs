@@ -0,0 +1,473 @@
+use crate::world::World;
+use std::any::{type_name, Any, TypeId};
+use std::fmt;
+
+/// A value in the `World` that a system reads or writes.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Access {
+    id: TypeId,
+    name: &'static str,
+}
+
+impl Access {
+    pub fn of<T: Any>() -> Self {
+        Self {
+            id: TypeId::of::<T>(),
+            name: type_name::<T>(),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+/// A named update function together with the world values it touches.
+///
+/// The declared reads and writes are only used to batch and order systems. They are not checked
+/// against what the function actually borrows: a system that borrows an undeclared value may share
+/// a batch with one that writes it, and the conflicting world borrow then panics at run time.
+pub struct System {
+    name: String,
+    reads: Vec<Access>,
+    writes: Vec<Access>,
+    before: Vec<String>,
+    after: Vec<String>,
+    run: Box<dyn FnMut(&World) + Send>,
+}
+
+impl System {
+    pub fn new(name: impl Into<String>, run: impl FnMut(&World) + Send + 'static) -> Self {
+        Self {
+            name: name.into(),
+            reads: vec![],
+            writes: vec![],
+            before: vec![],
+            after: vec![],
+            run: Box::new(run),
+        }
+    }
+
+    pub fn reads<T: Any>(mut self) -> Self {
+        self.reads.push(Access::of::<T>());
+        self
+    }
+
+    pub fn writes<T: Any>(mut self) -> Self {
+        self.writes.push(Access::of::<T>());
+        self
+    }
+
+    /// Runs this system before the named system of the same stage.
+    pub fn before(mut self, system: impl Into<String>) -> Self {
+        self.before.push(system.into());
+        self
+    }
+
+    /// Runs this system after the named system of the same stage.
+    pub fn after(mut self, system: impl Into<String>) -> Self {
+        self.after.push(system.into());
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The first value that one of the systems writes while the other reads or writes it.
+    fn conflict(&self, other: &System) -> Option<Access> {
+        let touches = |system: &System, access: &Access| system.reads.contains(access) || system.writes.contains(access);
+
+        self.writes.iter().find(|access| touches(other, access))
+            .or_else(|| other.writes.iter().find(|access| touches(self, access)))
+            .copied()
+    }
+}
+
+impl fmt::Debug for System {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("System")
+            .field("name", &self.name)
+            .field("reads", &self.reads)
+            .field("writes", &self.writes)
+            .field("before", &self.before)
+            .field("after", &self.after)
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ScheduleError {
+    /// Two systems access the same value, at least one of them mutably, without an ordering between them.
+    Conflict { first: String, second: String, access: &'static str },
+    /// The ordering constraints form a cycle through this system.
+    Cycle(String),
+    /// Two systems of the stage have this name, so ordering constraints cannot tell them apart.
+    DuplicateSystem(String),
+    /// An ordering constraint names a system that is not in the stage.
+    UnknownSystem(String),
+    UnknownStage(String),
+}
+
+impl fmt::Display for ScheduleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScheduleError::Conflict { first, second, access } => write!(
+                f,
+                "systems `{}` and `{}` both access {} and one of them writes it; order them with before or after",
+                first, second, access
+            ),
+            ScheduleError::Cycle(system) => write!(f, "system `{}` is part of an ordering cycle", system),
+            ScheduleError::DuplicateSystem(system) => write!(f, "more than one system named `{}` in this stage", system),
+            ScheduleError::UnknownSystem(system) => write!(f, "no system named `{}` in this stage", system),
+            ScheduleError::UnknownStage(stage) => write!(f, "no stage named `{}`", stage),
+        }
+    }
+}
+
+impl std::error::Error for ScheduleError {}
+
+/// A group of systems run together, either once per `Schedule::run` or at a fixed timestep.
+#[derive(Debug)]
+pub struct Stage {
+    name: String,
+    step: Option<f64>,
+    max_steps: Option<usize>,
+    accumulator: f64,
+    systems: Vec<System>,
+    batches: Option<Vec<Vec<usize>>>,
+}
+
+impl Stage {
+    /// A stage that runs once per `Schedule::run`.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            step: None,
+            max_steps: None,
+            accumulator: 0.0,
+            systems: vec![],
+            batches: None,
+        }
+    }
+
+    /// A stage that runs once for every whole `step` of elapsed time, carrying the remainder over.
+    pub fn fixed(name: impl Into<String>, step: f64) -> Self {
+        assert!(step > 0.0, "fixed stage step must be positive");
+        Self {
+            step: Some(step),
+            ..Self::new(name)
+        }
+    }
+
+    /// Limits a fixed stage to `steps` runs per `Schedule::run`. Whole steps beyond the limit are
+    /// dropped rather than carried over, so a long frame does not cause ever longer catch-up frames.
+    pub fn with_max_steps(mut self, steps: usize) -> Self {
+        self.max_steps = Some(steps);
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn add(&mut self, system: System) {
+        self.systems.push(system);
+        self.batches = None;
+    }
+
+    /// Orders the systems into batches that do not conflict, so each batch can run in parallel.
+    pub fn build(&mut self) -> Result<(), ScheduleError> {
+        let len = self.systems.len();
+        for (i, system) in self.systems.iter().enumerate() {
+            if self.systems[..i].iter().any(|other| other.name == system.name) {
+                return Err(ScheduleError::DuplicateSystem(system.name.clone()));
+            }
+        }
+
+        let position = |name: &str| {
+            self.systems
+                .iter()
+                .position(|system| system.name == name)
+                .ok_or_else(|| ScheduleError::UnknownSystem(name.to_string()))
+        };
+
+        let mut successors = vec![vec![]; len];
+        for (i, system) in self.systems.iter().enumerate() {
+            for before in &system.before {
+                successors[i].push(position(before)?);
+            }
+            for after in &system.after {
+                successors[position(after)?].push(i);
+            }
+        }
+
+        let mut incoming = vec![0; len];
+        successors.iter().flatten().for_each(|next| incoming[*next] += 1);
+
+        let mut level = vec![0; len];
+        let mut ready = (0..len).filter(|i| incoming[*i] == 0).collect::<Vec<_>>();
+        let mut sorted = 0;
+        while let Some(i) = ready.pop() {
+            sorted += 1;
+            for next in &successors[i] {
+                level[*next] = level[*next].max(level[i] + 1);
+                incoming[*next] -= 1;
+                if incoming[*next] == 0 {
+                    ready.push(*next);
+                }
+            }
+        }
+
+        if sorted < len {
+            let cyclic = (0..len).find(|i| incoming[*i] > 0).unwrap_or_default();
+            return Err(ScheduleError::Cycle(self.systems[cyclic].name.clone()));
+        }
+
+        for a in 0..len {
+            for b in a + 1..len {
+                if let Some(access) = self.systems[a].conflict(&self.systems[b]) {
+                    if !reaches(&successors, a, b) && !reaches(&successors, b, a) {
+                        return Err(ScheduleError::Conflict {
+                            first: self.systems[a].name.clone(),
+                            second: self.systems[b].name.clone(),
+                            access: access.name,
+                        });
+                    }
+                }
+            }
+        }
+
+        let mut batches = vec![vec![]; level.iter().max().map_or(0, |max| max + 1)];
+        level.iter().enumerate().for_each(|(i, level)| batches[*level].push(i));
+        self.batches = Some(batches);
+        Ok(())
+    }
+
+    fn run_once(&mut self, world: &World) {
+        let batches = self.batches.as_ref().expect("stage must be built before it is run");
+        let systems = &mut self.systems;
+
+        for batch in batches {
+            match batch.as_slice() {
+                [only] => (systems[*only].run)(world),
+                #[cfg(feature = "rayon")]
+                _ => rayon::scope(|scope| {
+                    systems
+                        .iter_mut()
+                        .enumerate()
+                        .filter(|(i, _)| batch.contains(i))
+                        .for_each(|(_, system)| {
+                            scope.spawn(move |_| (system.run)(world));
+                        });
+                }),
+                #[cfg(not(feature = "rayon"))]
+                _ => batch.iter().for_each(|i| (systems[*i].run)(world)),
+            }
+        }
+    }
+
+    fn run(&mut self, world: &World, elapsed: f64) {
+        match self.step {
+            Some(step) => {
+                self.accumulator += elapsed;
+                let mut steps = 0;
+                while self.accumulator >= step {
+                    if self.max_steps == Some(steps) {
+                        self.accumulator %= step;
+                        break;
+                    }
+                    self.accumulator -= step;
+                    self.run_once(world);
+                    steps += 1;
+                }
+            }
+            None => self.run_once(world),
+        }
+    }
+}
+
+fn reaches(successors: &[Vec<usize>], from: usize, to: usize) -> bool {
+    let mut visited = vec![false; successors.len()];
+    let mut stack = vec![from];
+    while let Some(i) = stack.pop() {
+        if i == to {
+            return true;
+        }
+        if !std::mem::replace(&mut visited[i], true) {
+            stack.extend(&successors[i]);
+        }
+    }
+    false
+}
+
+/// Runs stages of systems in order. With the `rayon` feature the systems of each batch are spread
+/// across the rayon thread pool, otherwise they run one after another.
+#[derive(Debug, Default)]
+pub struct Schedule {
+    stages: Vec<Stage>,
+}
+
+impl Schedule {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_stage(&mut self, stage: Stage) {
+        self.stages.push(stage);
+    }
+
+    pub fn add_system(&mut self, stage: &str, system: System) -> Result<(), ScheduleError> {
+        self.stages
+            .iter_mut()
+            .find(|s| s.name == stage)
+            .ok_or_else(|| ScheduleError::UnknownStage(stage.to_string()))?
+            .add(system);
+        Ok(())
+    }
+
+    pub fn build(&mut self) -> Result<(), ScheduleError> {
+        self.stages.iter_mut().try_for_each(Stage::build)
+    }
+
+    /// Runs every stage once, and fixed stages once per whole step in `elapsed`. Panics if the
+    /// schedule has changed since it was last built.
+    pub fn run(&mut self, world: &World, elapsed: f64) {
+        self.stages.iter_mut().for_each(|stage| stage.run(world, elapsed));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Debug, Default)]
+    struct Position(f64);
+
+    #[derive(Debug, Default)]
+    struct Velocity(f64);
+
+    #[derive(Debug, Default)]
+    struct Log(Vec<&'static str>);
+
+    fn world() -> World {
+        let mut world = World::new();
+        world.insert(Position(0.0));
+        world.insert(Velocity(0.0));
+        world.insert(Log::default());
+        world
+    }
+
+    #[test]
+    fn unordered_writes_conflict() {
+        let mut stage = Stage::new("update");
+        stage.add(System::new("a", |_| {}).writes::<Position>());
+        stage.add(System::new("b", |_| {}).reads::<Position>());
+
+        assert_eq!(
+            Err(ScheduleError::Conflict {
+                first: "a".to_string(),
+                second: "b".to_string(),
+                access: type_name::<Position>(),
+            }),
+            stage.build()
+        );
+    }
+
+    #[test]
+    fn ordering_constraints() {
+        let world = world();
+        let mut schedule = Schedule::new();
+        schedule.add_stage(Stage::new("update"));
+
+        schedule.add_system("update", System::new("move", |world| {
+            world.resource_mut::<Position>().0 += world.resource::<Velocity>().0;
+            world.resource_mut::<Log>().0.push("move");
+        }).reads::<Velocity>().writes::<Position>().writes::<Log>().after("accelerate")).unwrap();
+
+        schedule.add_system("update", System::new("accelerate", |world| {
+            world.resource_mut::<Velocity>().0 += 1.0;
+            world.resource_mut::<Log>().0.push("accelerate");
+        }).writes::<Velocity>().writes::<Log>()).unwrap();
+
+        schedule.build().unwrap();
+        schedule.run(&world, 0.0);
+        schedule.run(&world, 0.0);
+
+        assert_eq!(3.0, world.resource::<Position>().0);
+        assert_eq!(vec!["accelerate", "move", "accelerate", "move"], world.resource::<Log>().0);
+    }
+
+    #[test]
+    fn independent_systems_share_a_batch() {
+        let world = world();
+        let threads = Arc::new(Mutex::new(vec![]));
+        let mut stage = Stage::new("update");
+
+        for name in ["position", "velocity"] {
+            let threads = threads.clone();
+            let system = System::new(name, move |_| threads.lock().unwrap().push(std::thread::current().id()));
+            stage.add(if name == "position" { system.writes::<Position>() } else { system.writes::<Velocity>() });
+        }
+
+        stage.build().unwrap();
+        assert_eq!(Some(&vec![vec![0, 1]]), stage.batches.as_ref());
+
+        stage.run(&world, 0.0);
+        let threads = threads.lock().unwrap();
+        assert_eq!(2, threads.len());
+        #[cfg(feature = "rayon")]
+        assert!(!threads.contains(&std::thread::current().id()));
+        #[cfg(not(feature = "rayon"))]
+        assert!(threads.iter().all(|id| *id == std::thread::current().id()));
+    }
+
+    #[test]
+    fn cycles_and_unknown_systems() {
+        let mut stage = Stage::new("update");
+        stage.add(System::new("a", |_| {}).after("b"));
+        stage.add(System::new("b", |_| {}).after("a"));
+        assert!(matches!(stage.build(), Err(ScheduleError::Cycle(_))));
+
+        let mut stage = Stage::new("update");
+        stage.add(System::new("a", |_| {}).before("missing"));
+        assert_eq!(Err(ScheduleError::UnknownSystem("missing".to_string())), stage.build());
+
+        let mut stage = Stage::new("update");
+        stage.add(System::new("a", |_| {}));
+        stage.add(System::new("b", |_| {}).after("a"));
+        stage.add(System::new("a", |_| {}));
+        assert_eq!(Err(ScheduleError::DuplicateSystem("a".to_string())), stage.build());
+    }
+
+    #[test]
+    fn fixed_stage_runs_per_step() {
+        let world = world();
+        let mut schedule = Schedule::new();
+        schedule.add_stage(Stage::fixed("physics", 0.25));
+        schedule.add_system("physics", System::new("tick", |world| world.resource_mut::<Position>().0 += 1.0).writes::<Position>()).unwrap();
+        schedule.build().unwrap();
+
+        schedule.run(&world, 0.6);
+        assert_eq!(2.0, world.resource::<Position>().0);
+
+        schedule.run(&world, 0.4);
+        assert_eq!(4.0, world.resource::<Position>().0);
+    }
+
+    #[test]
+    fn fixed_stage_drops_steps_past_the_limit() {
+        let world = world();
+        let mut schedule = Schedule::new();
+        schedule.add_stage(Stage::fixed("physics", 0.25).with_max_steps(3));
+        schedule.add_system("physics", System::new("tick", |world| world.resource_mut::<Position>().0 += 1.0).writes::<Position>()).unwrap();
+        schedule.build().unwrap();
+
+        schedule.run(&world, 2.1);
+        assert_eq!(3.0, world.resource::<Position>().0);
+
+        schedule.run(&world, 0.2);
+        assert_eq!(4.0, world.resource::<Position>().0);
+    }
+}
@@ -0,0 +1,450 @@
+use crate::components::{Comp1, Comp2};
+
+/// Element types that the column kernels operate on.
+///
+/// The `*_slice` kernels default to the chunked scalar loop. The numeric lanes override them with
+/// AVX2/FMA intrinsics, picked at runtime when the CPU supports them.
+pub trait Lane: Copy + 'static {
+    fn add(self, rhs: Self) -> Self;
+    fn mul(self, rhs: Self) -> Self;
+    fn mul_add(self, a: Self, b: Self) -> Self;
+    fn min(self, rhs: Self) -> Self;
+    fn max(self, rhs: Self) -> Self;
+
+    fn add_slice(out: &mut [Self], rhs: &[Self]) {
+        scalar::zip1(out, rhs, Self::add)
+    }
+
+    fn mul_slice(out: &mut [Self], rhs: &[Self]) {
+        scalar::zip1(out, rhs, Self::mul)
+    }
+
+    fn mul_add_slice(out: &mut [Self], a: &[Self], b: &[Self]) {
+        scalar::zip2(out, a, b, Self::mul_add)
+    }
+
+    fn min_slice(out: &mut [Self], rhs: &[Self]) {
+        scalar::zip1(out, rhs, Self::min)
+    }
+
+    fn max_slice(out: &mut [Self], rhs: &[Self]) {
+        scalar::zip1(out, rhs, Self::max)
+    }
+}
+
+/// Overrides the slice kernels of a lane with the matching functions from `x86`.
+macro_rules! x86_kernels {
+    ($add:ident, $mul:ident, $mul_add:ident, $min:ident, $max:ident) => {
+        fn add_slice(out: &mut [Self], rhs: &[Self]) {
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            {
+                if x86::detected() {
+                    return unsafe { x86::$add(out, rhs) };
+                }
+            }
+            scalar::zip1(out, rhs, <Self as Lane>::add)
+        }
+
+        fn mul_slice(out: &mut [Self], rhs: &[Self]) {
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            {
+                if x86::detected() {
+                    return unsafe { x86::$mul(out, rhs) };
+                }
+            }
+            scalar::zip1(out, rhs, <Self as Lane>::mul)
+        }
+
+        fn mul_add_slice(out: &mut [Self], a: &[Self], b: &[Self]) {
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            {
+                if x86::detected() {
+                    return unsafe { x86::$mul_add(out, a, b) };
+                }
+            }
+            scalar::zip2(out, a, b, <Self as Lane>::mul_add)
+        }
+
+        fn min_slice(out: &mut [Self], rhs: &[Self]) {
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            {
+                if x86::detected() {
+                    return unsafe { x86::$min(out, rhs) };
+                }
+            }
+            scalar::zip1(out, rhs, <Self as Lane>::min)
+        }
+
+        fn max_slice(out: &mut [Self], rhs: &[Self]) {
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            {
+                if x86::detected() {
+                    return unsafe { x86::$max(out, rhs) };
+                }
+            }
+            scalar::zip1(out, rhs, <Self as Lane>::max)
+        }
+    };
+}
+
+macro_rules! float_lane {
+    ($t:ty, $($kernels:ident),*) => {
+        impl Lane for $t {
+            #[inline(always)]
+            fn add(self, rhs: Self) -> Self { self + rhs }
+
+            #[inline(always)]
+            fn mul(self, rhs: Self) -> Self { self * rhs }
+
+            #[inline(always)]
+            fn mul_add(self, a: Self, b: Self) -> Self { <$t>::mul_add(self, a, b) }
+
+            #[inline(always)]
+            fn min(self, rhs: Self) -> Self { if rhs < self { rhs } else { self } }
+
+            #[inline(always)]
+            fn max(self, rhs: Self) -> Self { if rhs > self { rhs } else { self } }
+
+            x86_kernels!($($kernels),*);
+        }
+    };
+}
+
+macro_rules! int_lane {
+    ($t:ty, $($kernels:ident),*) => {
+        impl Lane for $t {
+            #[inline(always)]
+            fn add(self, rhs: Self) -> Self { self.wrapping_add(rhs) }
+
+            #[inline(always)]
+            fn mul(self, rhs: Self) -> Self { self.wrapping_mul(rhs) }
+
+            #[inline(always)]
+            fn mul_add(self, a: Self, b: Self) -> Self { self.wrapping_mul(a).wrapping_add(b) }
+
+            #[inline(always)]
+            fn min(self, rhs: Self) -> Self { Ord::min(self, rhs) }
+
+            #[inline(always)]
+            fn max(self, rhs: Self) -> Self { Ord::max(self, rhs) }
+
+            x86_kernels!($($kernels),*);
+        }
+    };
+}
+
+float_lane!(f32, add_f32, mul_f32, mul_add_f32, min_f32, max_f32);
+float_lane!(f64, add_f64, mul_f64, mul_add_f64, min_f64, max_f64);
+int_lane!(i32, add_i32, mul_i32, mul_add_i32, min_i32, max_i32);
+int_lane!(u32, add_u32, mul_u32, mul_add_u32, min_u32, max_u32);
+
+/// Elements per chunk, wide enough for a 256-bit register of 32-bit lanes.
+pub const LANES: usize = 8;
+
+mod scalar {
+    use super::*;
+
+    #[inline(always)]
+    pub fn zip1<T: Copy, A: Copy, F: Fn(T, A) -> T>(out: &mut [T], a: &[A], f: F) {
+        let len = out.len().min(a.len());
+        let mut out = out[..len].chunks_exact_mut(LANES);
+        let mut a = a[..len].chunks_exact(LANES);
+
+        for (out, a) in (&mut out).zip(&mut a) {
+            for lane in 0..LANES {
+                out[lane] = f(out[lane], a[lane]);
+            }
+        }
+
+        for (out, a) in out.into_remainder().iter_mut().zip(a.remainder()) {
+            *out = f(*out, *a);
+        }
+    }
+
+    #[inline(always)]
+    pub fn zip2<T: Copy, A: Copy, B: Copy, F: Fn(T, A, B) -> T>(out: &mut [T], a: &[A], b: &[B], f: F) {
+        let len = out.len().min(a.len()).min(b.len());
+        let mut out = out[..len].chunks_exact_mut(LANES);
+        let mut a = a[..len].chunks_exact(LANES);
+        let mut b = b[..len].chunks_exact(LANES);
+
+        for ((out, a), b) in (&mut out).zip(&mut a).zip(&mut b) {
+            for lane in 0..LANES {
+                out[lane] = f(out[lane], a[lane], b[lane]);
+            }
+        }
+
+        for ((out, a), b) in out.into_remainder().iter_mut().zip(a.remainder()).zip(b.remainder()) {
+            *out = f(*out, *a, *b);
+        }
+    }
+}
+
+/// AVX2/FMA kernels. `Comp1` columns start on a register boundary, but the kernels also take
+/// arbitrary slices, so they use unaligned loads and stores, which cost nothing extra on aligned
+/// data. The tail that does not fill a register goes through the scalar `Lane` ops.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+mod x86 {
+    use super::Lane;
+    #[cfg(target_arch = "x86")]
+    use std::arch::x86::*;
+    #[cfg(target_arch = "x86_64")]
+    use std::arch::x86_64::*;
+
+    pub fn detected() -> bool {
+        is_x86_feature_detected!("avx2") && is_x86_feature_detected!("fma")
+    }
+
+    /// `out = op(out, rhs)`
+    macro_rules! binary {
+        ($name:ident, $t:ty, $width:expr, $load:ident, $store:ident, $op:ident, $lane:ident) => {
+            #[target_feature(enable = "avx2,fma")]
+            pub unsafe fn $name(out: &mut [$t], rhs: &[$t]) {
+                let len = out.len().min(rhs.len());
+                let vectors = len - len % $width;
+
+                for i in (0..vectors).step_by($width) {
+                    let a = $load(out.as_ptr().add(i) as *const _);
+                    let b = $load(rhs.as_ptr().add(i) as *const _);
+                    $store(out.as_mut_ptr().add(i) as *mut _, $op(b, a));
+                }
+
+                for i in vectors..len {
+                    out[i] = <$t as Lane>::$lane(out[i], rhs[i]);
+                }
+            }
+        };
+    }
+
+    /// `out = op(out, a, b)`
+    macro_rules! ternary {
+        ($name:ident, $t:ty, $width:expr, $load:ident, $store:ident, $op:ident, $lane:ident) => {
+            #[target_feature(enable = "avx2,fma")]
+            pub unsafe fn $name(out: &mut [$t], a: &[$t], b: &[$t]) {
+                let len = out.len().min(a.len()).min(b.len());
+                let vectors = len - len % $width;
+
+                for i in (0..vectors).step_by($width) {
+                    let o = $load(out.as_ptr().add(i) as *const _);
+                    let x = $load(a.as_ptr().add(i) as *const _);
+                    let y = $load(b.as_ptr().add(i) as *const _);
+                    $store(out.as_mut_ptr().add(i) as *mut _, $op(o, x, y));
+                }
+
+                for i in vectors..len {
+                    out[i] = <$t as Lane>::$lane(out[i], a[i], b[i]);
+                }
+            }
+        };
+    }
+
+    /// Wrapping `a * b + c` on 32-bit integer lanes, which has no single instruction.
+    #[target_feature(enable = "avx2")]
+    unsafe fn mul_add_epi32(a: __m256i, b: __m256i, c: __m256i) -> __m256i {
+        _mm256_add_epi32(_mm256_mullo_epi32(a, b), c)
+    }
+
+    // Float min/max take `rhs` first, so that `rhs` is only picked when strictly smaller or
+    // larger, matching the scalar ops.
+    binary!(add_f32, f32, 8, _mm256_loadu_ps, _mm256_storeu_ps, _mm256_add_ps, add);
+    binary!(mul_f32, f32, 8, _mm256_loadu_ps, _mm256_storeu_ps, _mm256_mul_ps, mul);
+    binary!(min_f32, f32, 8, _mm256_loadu_ps, _mm256_storeu_ps, _mm256_min_ps, min);
+    binary!(max_f32, f32, 8, _mm256_loadu_ps, _mm256_storeu_ps, _mm256_max_ps, max);
+    ternary!(mul_add_f32, f32, 8, _mm256_loadu_ps, _mm256_storeu_ps, _mm256_fmadd_ps, mul_add);
+
+    binary!(add_f64, f64, 4, _mm256_loadu_pd, _mm256_storeu_pd, _mm256_add_pd, add);
+    binary!(mul_f64, f64, 4, _mm256_loadu_pd, _mm256_storeu_pd, _mm256_mul_pd, mul);
+    binary!(min_f64, f64, 4, _mm256_loadu_pd, _mm256_storeu_pd, _mm256_min_pd, min);
+    binary!(max_f64, f64, 4, _mm256_loadu_pd, _mm256_storeu_pd, _mm256_max_pd, max);
+    ternary!(mul_add_f64, f64, 4, _mm256_loadu_pd, _mm256_storeu_pd, _mm256_fmadd_pd, mul_add);
+
+    binary!(add_i32, i32, 8, _mm256_loadu_si256, _mm256_storeu_si256, _mm256_add_epi32, add);
+    binary!(mul_i32, i32, 8, _mm256_loadu_si256, _mm256_storeu_si256, _mm256_mullo_epi32, mul);
+    binary!(min_i32, i32, 8, _mm256_loadu_si256, _mm256_storeu_si256, _mm256_min_epi32, min);
+    binary!(max_i32, i32, 8, _mm256_loadu_si256, _mm256_storeu_si256, _mm256_max_epi32, max);
+    ternary!(mul_add_i32, i32, 8, _mm256_loadu_si256, _mm256_storeu_si256, mul_add_epi32, mul_add);
+
+    binary!(add_u32, u32, 8, _mm256_loadu_si256, _mm256_storeu_si256, _mm256_add_epi32, add);
+    binary!(mul_u32, u32, 8, _mm256_loadu_si256, _mm256_storeu_si256, _mm256_mullo_epi32, mul);
+    binary!(min_u32, u32, 8, _mm256_loadu_si256, _mm256_storeu_si256, _mm256_min_epu32, min);
+    binary!(max_u32, u32, 8, _mm256_loadu_si256, _mm256_storeu_si256, _mm256_max_epu32, max);
+    ternary!(mul_add_u32, u32, 8, _mm256_loadu_si256, _mm256_storeu_si256, mul_add_epi32, mul_add);
+}
+
+/// Applies `f` element-wise in place over `LANES`-wide chunks, for ops without a `Lane` kernel.
+/// Stops at the shorter slice.
+pub fn zip1<T: Copy, A: Copy, F: Fn(T, A) -> T>(out: &mut [T], a: &[A], f: F) {
+    scalar::zip1(out, a, f)
+}
+
+/// Applies `f` element-wise in place over `LANES`-wide chunks, for ops without a `Lane` kernel.
+/// Stops at the shortest slice.
+pub fn zip2<T: Copy, A: Copy, B: Copy, F: Fn(T, A, B) -> T>(out: &mut [T], a: &[A], b: &[B], f: F) {
+    scalar::zip2(out, a, b, f)
+}
+
+impl<ID, T: Lane> Comp1<ID, T> {
+    /// Panics if the columns differ in length.
+    pub fn add_assign(&mut self, rhs: &Comp1<ID, T>) {
+        assert_eq!(self.len(), rhs.len(), "column lengths differ");
+        T::add_slice(&mut self.values, &rhs.values);
+    }
+
+    /// Panics if the columns differ in length.
+    pub fn mul_assign(&mut self, rhs: &Comp1<ID, T>) {
+        assert_eq!(self.len(), rhs.len(), "column lengths differ");
+        T::mul_slice(&mut self.values, &rhs.values);
+    }
+
+    /// `self = self * a + b`. Panics if the columns differ in length.
+    pub fn fma_assign(&mut self, a: &Comp1<ID, T>, b: &Comp1<ID, T>) {
+        assert_eq!(self.len(), a.len(), "column lengths differ");
+        assert_eq!(self.len(), b.len(), "column lengths differ");
+        T::mul_add_slice(&mut self.values, &a.values, &b.values);
+    }
+
+    /// Panics if the columns differ in length.
+    pub fn min_assign(&mut self, rhs: &Comp1<ID, T>) {
+        assert_eq!(self.len(), rhs.len(), "column lengths differ");
+        T::min_slice(&mut self.values, &rhs.values);
+    }
+
+    /// Panics if the columns differ in length.
+    pub fn max_assign(&mut self, rhs: &Comp1<ID, T>) {
+        assert_eq!(self.len(), rhs.len(), "column lengths differ");
+        T::max_slice(&mut self.values, &rhs.values);
+    }
+
+    /// Replaces each value with the value from `rhs` wherever `mask` is true. Panics if the
+    /// columns differ in length.
+    pub fn select_assign(&mut self, mask: &Comp1<ID, bool>, rhs: &Comp1<ID, T>) {
+        assert_eq!(self.len(), mask.len(), "column lengths differ");
+        assert_eq!(self.len(), rhs.len(), "column lengths differ");
+        zip2(&mut self.values, &mask.values, &rhs.values, |v, m, r| if m { r } else { v });
+    }
+}
+
+impl<ID, T1: Lane, T2: Lane> Comp2<ID, T1, T2> {
+    pub fn add_assign(&mut self, rhs: &Comp2<ID, T1, T2>) {
+        self.0.add_assign(&rhs.0);
+        self.1.add_assign(&rhs.1);
+    }
+
+    pub fn mul_assign(&mut self, rhs: &Comp2<ID, T1, T2>) {
+        self.0.mul_assign(&rhs.0);
+        self.1.mul_assign(&rhs.1);
+    }
+
+    /// `self = self * a + b`
+    pub fn fma_assign(&mut self, a: &Comp2<ID, T1, T2>, b: &Comp2<ID, T1, T2>) {
+        self.0.fma_assign(&a.0, &b.0);
+        self.1.fma_assign(&a.1, &b.1);
+    }
+
+    pub fn min_assign(&mut self, rhs: &Comp2<ID, T1, T2>) {
+        self.0.min_assign(&rhs.0);
+        self.1.min_assign(&rhs.1);
+    }
+
+    pub fn max_assign(&mut self, rhs: &Comp2<ID, T1, T2>) {
+        self.0.max_assign(&rhs.0);
+        self.1.max_assign(&rhs.1);
+    }
+
+    pub fn select_assign(&mut self, mask: &Comp1<ID, bool>, rhs: &Comp2<ID, T1, T2>) {
+        self.0.select_assign(mask, &rhs.0);
+        self.1.select_assign(mask, &rhs.1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn comp<T>(values: Vec<T>) -> Comp1<(), T> {
+        let mut comp = Comp1::default();
+        comp.values = values.into();
+        comp
+    }
+
+    #[test]
+    fn add_with_remainder() {
+        let mut a = comp((0..19).map(|i| i as f32).collect());
+        let b = comp(vec![1.0f32; 19]);
+
+        a.add_assign(&b);
+
+        assert_eq!((1..20).map(|i| i as f32).collect::<Vec<_>>(), a.values);
+    }
+
+    #[test]
+    fn fma_f64() {
+        let mut a = comp(vec![2.0f64; 11]);
+        let b = comp(vec![3.0f64; 11]);
+        let c = comp(vec![0.5f64; 11]);
+
+        a.fma_assign(&b, &c);
+
+        assert_eq!(vec![6.5; 11], a.values);
+    }
+
+    #[test]
+    fn min_max_u32() {
+        let mut a = comp((0..10u32).collect());
+        let b = comp(vec![4u32; 10]);
+
+        a.min_assign(&b);
+        assert_eq!(vec![0, 1, 2, 3, 4, 4, 4, 4, 4, 4], a.values);
+
+        a.max_assign(&comp(vec![2u32; 10]));
+        assert_eq!(vec![2, 2, 2, 3, 4, 4, 4, 4, 4, 4], a.values);
+    }
+
+    #[test]
+    fn select_i32() {
+        let mut a = comp(vec![1i32; 9]);
+        let b = comp(vec![-1i32; 9]);
+        let mask = comp((0..9).map(|i| i % 2 == 0).collect());
+
+        a.select_assign(&mask, &b);
+
+        assert_eq!(vec![-1, 1, -1, 1, -1, 1, -1, 1, -1], a.values);
+    }
+
+    #[test]
+    fn integer_kernels_match_scalar_ops() {
+        let values = (0..37u32).map(|i| i.wrapping_mul(0x1234_5678u32).rotate_left(i)).collect::<Vec<_>>();
+        let rhs = values.iter().rev().copied().collect::<Vec<_>>();
+
+        for (kernel, op) in [(u32::min_slice as fn(&mut [u32], &[u32]), Ord::min as fn(u32, u32) -> u32), (u32::max_slice, Ord::max), (u32::mul_slice, Lane::mul)].iter() {
+            let mut out = values.clone();
+            kernel(&mut out, &rhs);
+            let expected = values.iter().zip(&rhs).map(|(a, b)| op(*a, *b)).collect::<Vec<_>>();
+            assert_eq!(expected, out);
+        }
+
+        let mut a = comp(values.iter().map(|v| *v as i32).collect());
+        let b = comp(rhs.iter().map(|v| *v as i32).collect());
+        let expected = a.values.iter().zip(&b.values).map(|(a, b)| Ord::min(*a, *b)).collect::<Vec<_>>();
+        a.min_assign(&b);
+        assert_eq!(expected, a.values);
+    }
+
+    #[test]
+    #[should_panic(expected = "column lengths differ")]
+    fn mismatched_lengths_panic() {
+        let mut a = comp(vec![1.0f32; 9]);
+        a.add_assign(&comp(vec![1.0f32; 8]));
+    }
+
+    #[test]
+    fn comp2_mul() {
+        let mut a = Comp2::<(), f32, i32>(comp(vec![2.0; 3]), comp(vec![3; 3]));
+        let b = Comp2::<(), f32, i32>(comp(vec![0.5; 3]), comp(vec![-2; 3]));
+
+        a.mul_assign(&b);
+
+        assert_eq!(vec![1.0; 3], a.0.values);
+        assert_eq!(vec![-6; 3], a.1.values);
+    }
+}
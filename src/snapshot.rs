@@ -0,0 +1,449 @@
+use crate::components::Comp1;
+use std::fmt;
+use std::io::{self, Write};
+use std::mem::size_of;
+
+const MAGIC: &[u8; 4] = b"SECS";
+const FORMAT_VERSION: u32 = 1;
+
+/// Marks the byte order of the column data, which is written natively.
+const BYTE_ORDER: u32 = if cfg!(target_endian = "little") { 1 } else { 2 };
+
+/// The smallest possible column header, used to bound how many columns a buffer can hold.
+const MIN_COLUMN_HEADER: usize = 4 + 4 + 8 + 8;
+
+/// Column data starts on this boundary, relative to the start of the snapshot.
+pub const DATA_ALIGN: usize = 16;
+
+/// Types that can be written as raw bytes and read back by reinterpreting them.
+///
+/// # Safety
+/// Implementors must be `Copy`, contain no padding or pointers, and be valid for any bit pattern.
+pub unsafe trait Pod: Copy + 'static {}
+
+macro_rules! pod {
+    ($($t:ty),*) => { $(unsafe impl Pod for $t {})* };
+}
+
+pod!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64);
+
+unsafe impl<T: Pod, const N: usize> Pod for [T; N] {}
+
+/// Collects columns and writes them out as a snapshot.
+///
+/// Layout, with header integers in little-endian and column data in native byte order. The byte
+/// order is recorded as 1 for little-endian and 2 for big-endian, and a reader with the other
+/// byte order rejects the snapshot.
+///
+/// ```text
+/// magic "SECS" | format version u32 | byte order u32 | schema version u32 | column count u32
+/// per column:  name length u32 | name | element size u32 | length u64 | checksum u64
+///              | padding to DATA_ALIGN | data
+/// ```
+#[derive(Debug, Clone)]
+pub struct SnapshotWriter<'a> {
+    schema_version: u32,
+    columns: Vec<Column<'a>>,
+}
+
+#[derive(Debug, Clone)]
+struct Column<'a> {
+    name: String,
+    elem_size: usize,
+    len: usize,
+    data: &'a [u8],
+}
+
+impl<'a> SnapshotWriter<'a> {
+    pub fn new(schema_version: u32) -> Self {
+        Self {
+            schema_version,
+            columns: vec![],
+        }
+    }
+
+    pub fn column<ID, T: Pod>(&mut self, name: &str, column: &'a Comp1<ID, T>) -> &mut Self {
+        self.columns.push(Column {
+            name: name.to_string(),
+            elem_size: size_of::<T>(),
+            len: column.len(),
+            data: as_bytes(&column.values),
+        });
+        self
+    }
+
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let mut out = Counted { writer, offset: 0 };
+
+        out.write(MAGIC)?;
+        out.write(&FORMAT_VERSION.to_le_bytes())?;
+        out.write(&BYTE_ORDER.to_le_bytes())?;
+        out.write(&self.schema_version.to_le_bytes())?;
+        out.write(&(self.columns.len() as u32).to_le_bytes())?;
+
+        for column in &self.columns {
+            out.write(&(column.name.len() as u32).to_le_bytes())?;
+            out.write(column.name.as_bytes())?;
+            out.write(&(column.elem_size as u32).to_le_bytes())?;
+            out.write(&(column.len as u64).to_le_bytes())?;
+            out.write(&checksum(column.data).to_le_bytes())?;
+            out.write(&[0; DATA_ALIGN][..padding(out.offset)])?;
+            out.write(column.data)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        self.write_to(&mut bytes).expect("writing to a Vec cannot fail");
+        bytes
+    }
+}
+
+/// A parsed snapshot borrowing its column data from the underlying buffer, e.g. a memory map.
+#[derive(Debug, Clone)]
+pub struct Snapshot<'a> {
+    schema_version: u32,
+    columns: Vec<ColumnRef<'a>>,
+}
+
+#[derive(Debug, Clone)]
+struct ColumnRef<'a> {
+    name: &'a str,
+    elem_size: usize,
+    len: usize,
+    data: &'a [u8],
+}
+
+impl<'a> Snapshot<'a> {
+    /// Reads the header and verifies the checksum of every column, so lookups do not repeat it.
+    pub fn parse(bytes: &'a [u8]) -> Result<Self, SnapshotError> {
+        let mut reader = Reader { bytes, offset: 0 };
+
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(SnapshotError::BadMagic);
+        }
+
+        let format = reader.u32()?;
+        if format != FORMAT_VERSION {
+            return Err(SnapshotError::UnsupportedFormat(format));
+        }
+
+        let byte_order = reader.u32()?;
+        if byte_order != BYTE_ORDER {
+            return Err(SnapshotError::ByteOrder);
+        }
+
+        let schema_version = reader.u32()?;
+        let count = reader.u32()? as usize;
+
+        let mut columns = Vec::with_capacity(count.min(reader.remaining() / MIN_COLUMN_HEADER));
+        for _ in 0..count {
+            let name_len = reader.u32()? as usize;
+            let name = std::str::from_utf8(reader.take(name_len)?)
+                .map_err(|_| SnapshotError::InvalidName)?;
+            let elem_size = reader.u32()? as usize;
+            let len = reader.u64()? as usize;
+            let stored = reader.u64()?;
+            reader.take(padding(reader.offset))?;
+            let size = elem_size.checked_mul(len).ok_or(SnapshotError::Truncated)?;
+            let data = reader.take(size)?;
+            if checksum(data) != stored {
+                return Err(SnapshotError::Checksum(name.to_string()));
+            }
+
+            columns.push(ColumnRef { name, elem_size, len, data });
+        }
+
+        Ok(Self { schema_version, columns })
+    }
+
+    pub fn schema_version(&self) -> u32 {
+        self.schema_version
+    }
+
+    pub fn column_names(&self) -> impl Iterator<Item = &'a str> + '_ {
+        self.columns.iter().map(|c| c.name)
+    }
+
+    /// Compares the stored columns against those an entity table expects.
+    pub fn diff(&self, expected: &[&str]) -> SchemaDiff {
+        SchemaDiff {
+            missing: expected.iter()
+                .filter(|name| !self.columns.iter().any(|c| c.name == **name))
+                .map(|name| name.to_string())
+                .collect(),
+            extra: self.columns.iter()
+                .filter(|c| !expected.contains(&c.name))
+                .map(|c| c.name.to_string())
+                .collect(),
+        }
+    }
+
+    /// Borrows the column data in place. Fails with `Misaligned` if the buffer is not aligned for `T`.
+    pub fn column<T: Pod>(&self, name: &str) -> Result<&'a [T], SnapshotError> {
+        let column = self.find::<T>(name)?;
+
+        if !column.data.as_ptr().cast::<T>().is_aligned() {
+            return Err(SnapshotError::Misaligned(name.to_string()));
+        }
+
+        Ok(unsafe { std::slice::from_raw_parts(column.data.as_ptr() as *const T, column.len) })
+    }
+
+    /// Copies the column data into `column`, regardless of buffer alignment.
+    pub fn load<ID, T: Pod>(&self, name: &str, column: &mut Comp1<ID, T>) -> Result<(), SnapshotError> {
+        let source = self.find::<T>(name)?;
+
+        column.values.clear();
+        column.values.reserve(source.len);
+        unsafe {
+            std::ptr::copy_nonoverlapping(
+                source.data.as_ptr(),
+                column.values.as_mut_ptr() as *mut u8,
+                source.data.len(),
+            );
+            column.values.set_len(source.len);
+        }
+
+        Ok(())
+    }
+
+    fn find<T: Pod>(&self, name: &str) -> Result<&ColumnRef<'a>, SnapshotError> {
+        let column = self.columns.iter()
+            .find(|c| c.name == name)
+            .ok_or_else(|| SnapshotError::MissingColumn(name.to_string()))?;
+
+        if column.elem_size != size_of::<T>() {
+            return Err(SnapshotError::ElementSize {
+                column: name.to_string(),
+                expected: size_of::<T>(),
+                found: column.elem_size,
+            });
+        }
+
+        Ok(column)
+    }
+}
+
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct SchemaDiff {
+    pub missing: Vec<String>,
+    pub extra: Vec<String>,
+}
+
+impl SchemaDiff {
+    pub fn is_empty(&self) -> bool {
+        self.missing.is_empty() && self.extra.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum SnapshotError {
+    BadMagic,
+    UnsupportedFormat(u32),
+    /// The snapshot was written on a machine with the other byte order.
+    ByteOrder,
+    Truncated,
+    /// A column name is not valid UTF-8.
+    InvalidName,
+    MissingColumn(String),
+    ElementSize { column: String, expected: usize, found: usize },
+    Checksum(String),
+    Misaligned(String),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::BadMagic => write!(f, "not a snapshot"),
+            SnapshotError::UnsupportedFormat(v) => write!(f, "unsupported snapshot format version {}", v),
+            SnapshotError::ByteOrder => write!(f, "snapshot was written with a different byte order"),
+            SnapshotError::Truncated => write!(f, "snapshot is truncated"),
+            SnapshotError::InvalidName => write!(f, "snapshot column name is not valid UTF-8"),
+            SnapshotError::MissingColumn(c) => write!(f, "snapshot has no column `{}`", c),
+            SnapshotError::ElementSize { column, expected, found } => write!(
+                f,
+                "column `{}` has {}-byte elements, expected {}",
+                column, found, expected
+            ),
+            SnapshotError::Checksum(c) => write!(f, "checksum mismatch in column `{}`", c),
+            SnapshotError::Misaligned(c) => write!(f, "column `{}` is not aligned for its element type", c),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+struct Counted<'a, W> {
+    writer: &'a mut W,
+    offset: usize,
+}
+
+impl<'a, W: Write> Counted<'a, W> {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.writer.write_all(bytes)?;
+        self.offset += bytes.len();
+        Ok(())
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], SnapshotError> {
+        let end = self.offset.checked_add(n).ok_or(SnapshotError::Truncated)?;
+        let bytes = self.bytes.get(self.offset..end).ok_or(SnapshotError::Truncated)?;
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    fn remaining(&self) -> usize {
+        self.bytes.len() - self.offset
+    }
+
+    fn u32(&mut self) -> Result<u32, SnapshotError> {
+        let mut buf = [0; 4];
+        buf.copy_from_slice(self.take(4)?);
+        Ok(u32::from_le_bytes(buf))
+    }
+
+    fn u64(&mut self) -> Result<u64, SnapshotError> {
+        let mut buf = [0; 8];
+        buf.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(buf))
+    }
+}
+
+fn as_bytes<T: Pod>(values: &[T]) -> &[u8] {
+    unsafe { std::slice::from_raw_parts(values.as_ptr() as *const u8, std::mem::size_of_val(values)) }
+}
+
+fn padding(offset: usize) -> usize {
+    (DATA_ALIGN - offset % DATA_ALIGN) % DATA_ALIGN
+}
+
+/// FNV-1a
+fn checksum(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::allocators::FixedAllocator;
+    use crate::Insert;
+
+    fn columns() -> (Comp1<(), f64>, Comp1<(), u32>) {
+        let mut a = FixedAllocator::<()>::default();
+        let mut mass = Comp1::default();
+        let mut count = Comp1::default();
+
+        for i in 0..5 {
+            let id = a.create();
+            mass.insert(id, i as f64 * 1.5);
+            count.insert(id, i * 2);
+        }
+
+        (mass, count)
+    }
+
+    /// Copies into a buffer aligned like a memory map would be.
+    fn aligned(bytes: &[u8]) -> Vec<u128> {
+        let mut buffer = vec![0u128; bytes.len() / 16 + 1];
+        unsafe {
+            std::ptr::copy_nonoverlapping(bytes.as_ptr(), buffer.as_mut_ptr() as *mut u8, bytes.len());
+        }
+        buffer
+    }
+
+    fn view(buffer: &[u128], len: usize) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(buffer.as_ptr() as *const u8, len) }
+    }
+
+    #[test]
+    fn round_trip_in_place() {
+        let (mass, count) = columns();
+        let bytes = SnapshotWriter::new(3)
+            .column("mass", &mass)
+            .column("count", &count)
+            .to_bytes();
+
+        let buffer = aligned(&bytes);
+        let snapshot = Snapshot::parse(view(&buffer, bytes.len())).unwrap();
+
+        assert_eq!(3, snapshot.schema_version());
+        assert_eq!(&mass.values[..], snapshot.column::<f64>("mass").unwrap());
+        assert_eq!(&count.values[..], snapshot.column::<u32>("count").unwrap());
+
+        let mut loaded = Comp1::<(), u32>::default();
+        snapshot.load("count", &mut loaded).unwrap();
+        assert_eq!(count.values, loaded.values);
+    }
+
+    #[test]
+    fn schema_diff() {
+        let (mass, count) = columns();
+        let bytes = SnapshotWriter::new(1)
+            .column("mass", &mass)
+            .column("count", &count)
+            .to_bytes();
+        let snapshot = Snapshot::parse(&bytes).unwrap();
+
+        let diff = snapshot.diff(&["mass", "radius"]);
+        assert_eq!(vec!["radius".to_string()], diff.missing);
+        assert_eq!(vec!["count".to_string()], diff.extra);
+        assert!(snapshot.diff(&["count", "mass"]).is_empty());
+    }
+
+    #[test]
+    fn detects_corruption_and_type_mismatch() {
+        let (mass, _) = columns();
+        let mut bytes = SnapshotWriter::new(1).column("mass", &mass).to_bytes();
+
+        let snapshot = Snapshot::parse(&bytes).unwrap();
+        assert_eq!(
+            Err(SnapshotError::ElementSize { column: "mass".to_string(), expected: 4, found: 8 }),
+            snapshot.load("mass", &mut Comp1::<(), u32>::default())
+        );
+        assert_eq!(
+            Err(SnapshotError::MissingColumn("radius".to_string())),
+            snapshot.load("radius", &mut Comp1::<(), f64>::default())
+        );
+
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+        assert_eq!(Err(SnapshotError::Checksum("mass".to_string())), Snapshot::parse(&bytes).map(|_| ()));
+
+        assert_eq!(Err(SnapshotError::Truncated), Snapshot::parse(&bytes[..bytes.len() - 1]).map(|_| ()));
+        assert_eq!(Err(SnapshotError::BadMagic), Snapshot::parse(b"nope").map(|_| ()));
+    }
+
+    #[test]
+    fn rejects_bad_headers() {
+        let (mass, _) = columns();
+        let bytes = SnapshotWriter::new(1).column("mass", &mass).to_bytes();
+
+        let mut huge_count = bytes[..20].to_vec();
+        huge_count[16..20].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(Err(SnapshotError::Truncated), Snapshot::parse(&huge_count).map(|_| ()));
+
+        let mut newer = bytes.clone();
+        newer[4..8].copy_from_slice(&2u32.to_le_bytes());
+        assert_eq!(Err(SnapshotError::UnsupportedFormat(2)), Snapshot::parse(&newer).map(|_| ()));
+
+        let mut swapped = bytes.clone();
+        swapped[8..12].reverse();
+        assert_eq!(Err(SnapshotError::ByteOrder), Snapshot::parse(&swapped).map(|_| ()));
+
+        let mut bad_name = bytes;
+        bad_name[24] = 0xff;
+        assert_eq!(Err(SnapshotError::InvalidName), Snapshot::parse(&bad_name).map(|_| ()));
+    }
+}
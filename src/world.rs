@@ -0,0 +1,359 @@
+use crate::allocators::Allocator;
+use crate::{ColumnLengthError, Table};
+use std::any::{type_name, Any, TypeId};
+use std::collections::HashMap;
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Mutex, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard, TryLockError};
+
+/// A table of entity columns together with the allocator that hands out its ids.
+pub trait Entity: Default + Send + Sync + 'static {
+    type Allocator: Allocator<Self> + Default + Send + Sync + 'static;
+}
+
+/// The id type handed out for entities of `E`.
+pub type EntityId<E> = <<E as Entity>::Allocator as Allocator<E>>::Id;
+
+/// Owns entity tables, their allocators and singleton resources, each keyed by type.
+///
+/// Accessors take `&self` and check borrows at runtime, so a system can hold `table_mut::<Body>()`
+/// while reading `table::<System>()`. Conflicting borrows panic instead of blocking.
+#[derive(Default)]
+pub struct World {
+    values: HashMap<TypeId, RwLock<Box<dyn Any + Send + Sync>>>,
+    pending: Mutex<HashMap<TypeId, Box<dyn Pending>>>,
+    tables: Vec<(TypeId, LengthCheck)>,
+}
+
+type LengthCheck = fn(&World) -> Result<(), ColumnLengthError>;
+
+/// The rows of reserved entities of one type, waiting for `World::flush`.
+trait Pending: Send {
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    fn insert(self: Box<Self>, world: &World);
+}
+
+struct PendingRows<E: Entity + Table>(Vec<(EntityId<E>, E::Row)>);
+
+impl<E: Entity + Table> Pending for PendingRows<E>
+where
+    E::Row: Send + 'static,
+    EntityId<E>: Send + 'static,
+{
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn insert(self: Box<Self>, world: &World) {
+        let alloc = world.alloc::<E>();
+        let mut table = world.table_mut::<E>();
+        for (id, row) in self.0 {
+            if let Some(index) = alloc.row(&id) {
+                table.insert_row(index, row);
+            }
+        }
+    }
+}
+
+/// Shared access to a value stored in a `World`.
+pub struct Res<'a, T> {
+    guard: RwLockReadGuard<'a, Box<dyn Any + Send + Sync>>,
+    marker: PhantomData<&'a T>,
+}
+
+/// Exclusive access to a value stored in a `World`.
+pub struct ResMut<'a, T> {
+    guard: RwLockWriteGuard<'a, Box<dyn Any + Send + Sync>>,
+    marker: PhantomData<&'a mut T>,
+}
+
+impl<T: 'static> Deref for Res<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.guard.downcast_ref().expect("world values are keyed by their type")
+    }
+}
+
+impl<T: 'static> Deref for ResMut<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.guard.downcast_ref().expect("world values are keyed by their type")
+    }
+}
+
+impl<T: 'static> DerefMut for ResMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.guard.downcast_mut().expect("world values are keyed by their type")
+    }
+}
+
+impl<T: fmt::Debug + 'static> fmt::Debug for Res<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        T::fmt(self, f)
+    }
+}
+
+impl<T: fmt::Debug + 'static> fmt::Debug for ResMut<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        T::fmt(self, f)
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum WorldError {
+    /// Nothing of this type has been registered or inserted.
+    Missing(&'static str),
+    /// The value is already borrowed in a conflicting way.
+    Borrowed(&'static str),
+}
+
+impl fmt::Display for WorldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WorldError::Missing(name) => write!(f, "{} has not been added to the world", name),
+            WorldError::Borrowed(name) => write!(f, "{} is already borrowed", name),
+        }
+    }
+}
+
+impl std::error::Error for WorldError {}
+
+impl World {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an empty table and allocator for `E`, and includes the table in `check_lengths`. Does
+    /// nothing if `E` is already registered.
+    pub fn register<E: Entity + Table>(&mut self) {
+        self.register_unchecked::<E>();
+        if !self.tables.iter().any(|(id, _)| *id == TypeId::of::<E>()) {
+            self.tables.push((TypeId::of::<E>(), |world| world.table::<E>().check_lengths(&*world.alloc::<E>())));
+        }
+    }
+
+    /// Like `register`, for tables that do not implement `Table`. `check_lengths` skips them.
+    pub fn register_unchecked<E: Entity>(&mut self) {
+        if !self.contains::<E>() {
+            self.insert(E::default());
+            self.insert(E::Allocator::default());
+        }
+    }
+
+    /// Checks every table added with `register` against its allocator.
+    pub fn check_lengths(&self) -> Result<(), Vec<ColumnLengthError>> {
+        let errors = self.tables.iter()
+            .filter_map(|(_, check)| check(self).err())
+            .collect::<Vec<_>>();
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Adds or replaces a singleton resource.
+    pub fn insert<R: Any + Send + Sync>(&mut self, value: R) {
+        self.values.insert(TypeId::of::<R>(), RwLock::new(Box::new(value)));
+    }
+
+    pub fn contains<R: Any>(&self) -> bool {
+        self.values.contains_key(&TypeId::of::<R>())
+    }
+
+    pub fn table<E: Entity>(&self) -> Res<'_, E> {
+        self.get()
+    }
+
+    pub fn table_mut<E: Entity>(&self) -> ResMut<'_, E> {
+        self.get_mut()
+    }
+
+    pub fn alloc<E: Entity>(&self) -> Res<'_, E::Allocator> {
+        self.get()
+    }
+
+    /// Flushes any reserved entities of `E` first, so that they are created with their rows
+    /// before the allocator can create or kill anything else.
+    pub fn alloc_mut<E: Entity>(&self) -> ResMut<'_, E::Allocator> {
+        self.flush::<E>();
+        self.get_mut()
+    }
+
+    pub fn resource<R: Any + Send + Sync>(&self) -> Res<'_, R> {
+        self.get()
+    }
+
+    pub fn resource_mut<R: Any + Send + Sync>(&self) -> ResMut<'_, R> {
+        self.get_mut()
+    }
+
+    /// Creates an entity and fills in its row of the table, after flushing any reserved entities.
+    pub fn create<E: Entity + Table>(&self, row: E::Row) -> EntityId<E> {
+        let mut alloc = self.alloc_mut::<E>();
+        let id = alloc.create();
+        let index = alloc.row(&id).expect("newly created id is alive");
+        self.table_mut::<E>().insert_row(index, row);
+        id
+    }
+
+    /// Reserves the id of an entity whose row is inserted by the next `flush::<E>()`. Only borrows
+    /// the allocator immutably, so systems running in parallel can reserve ids of the same type.
+    ///
+    /// Reserved entities are created, with their rows, in the order they were reserved. Borrowing
+    /// the allocator with `alloc_mut` flushes them as well.
+    pub fn reserve<E: Entity + Table>(&self, row: E::Row) -> EntityId<E>
+    where
+        E::Row: Send + 'static,
+        EntityId<E>: Send + 'static,
+    {
+        let mut pending = self.pending.lock().unwrap_or_else(PoisonError::into_inner);
+        let id = self.alloc::<E>().reserve();
+        pending.entry(TypeId::of::<E>())
+            .or_insert_with(|| Box::new(PendingRows::<E>(vec![])))
+            .as_any_mut()
+            .downcast_mut::<PendingRows<E>>()
+            .expect("pending rows are keyed by their entity type")
+            .0
+            .push((id, row));
+        id
+    }
+
+    /// Creates the entities reserved with `reserve` and inserts their rows. Panics if the table
+    /// is borrowed while there are rows to insert.
+    pub fn flush<E: Entity>(&self) {
+        let mut pending = self.pending.lock().unwrap_or_else(PoisonError::into_inner);
+        self.get_mut::<E::Allocator>().flush();
+        if let Some(rows) = pending.remove(&TypeId::of::<E>()) {
+            rows.insert(self);
+        }
+    }
+
+    /// Panics if `R` is missing or mutably borrowed.
+    pub fn get<R: Any>(&self) -> Res<'_, R> {
+        self.try_get().unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Panics if `R` is missing or borrowed.
+    pub fn get_mut<R: Any>(&self) -> ResMut<'_, R> {
+        self.try_get_mut().unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn try_get<R: Any>(&self) -> Result<Res<'_, R>, WorldError> {
+        let guard = match self.lock::<R>()?.try_read() {
+            Ok(guard) => guard,
+            Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner(),
+            Err(TryLockError::WouldBlock) => return Err(WorldError::Borrowed(type_name::<R>())),
+        };
+
+        Ok(Res { guard, marker: PhantomData })
+    }
+
+    pub fn try_get_mut<R: Any>(&self) -> Result<ResMut<'_, R>, WorldError> {
+        let guard = match self.lock::<R>()?.try_write() {
+            Ok(guard) => guard,
+            Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner(),
+            Err(TryLockError::WouldBlock) => return Err(WorldError::Borrowed(type_name::<R>())),
+        };
+
+        Ok(ResMut { guard, marker: PhantomData })
+    }
+
+    fn lock<R: Any>(&self) -> Result<&RwLock<Box<dyn Any + Send + Sync>>, WorldError> {
+        self.values
+            .get(&TypeId::of::<R>())
+            .ok_or(WorldError::Missing(type_name::<R>()))
+    }
+}
+
+impl fmt::Debug for World {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("World").field("values", &self.values.len()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::allocators::{FixedAllocator, Indexes};
+    use crate::components::Comp1;
+    use crate::{Get1, Insert};
+
+    #[derive(Debug, Default)]
+    struct Body {
+        mass: Comp1<Self, f64>,
+    }
+
+    impl Entity for Body {
+        type Allocator = FixedAllocator<Self>;
+    }
+
+    impl Table for Body {
+        type Row = f64;
+
+        fn insert_row<I: Indexes<Self>>(&mut self, id: I, row: f64) {
+            self.mass.insert(id, row);
+        }
+
+        fn column_lengths(&self) -> Vec<(&'static str, usize)> {
+            vec![("mass", self.mass.len())]
+        }
+    }
+
+    #[derive(Debug, Default, PartialEq)]
+    struct Time(f64);
+
+    #[test]
+    fn register_and_split_borrows() {
+        let mut world = World::new();
+        world.register::<Body>();
+        world.insert(Time(2.0));
+
+        let id = world.create::<Body>(3.0);
+
+        {
+            let mut body = world.table_mut::<Body>();
+            let time = world.resource::<Time>();
+            *body.mass.get_mut(id).unwrap() *= time.0;
+        }
+
+        assert_eq!(Some(&6.0), world.table::<Body>().mass.get(id));
+        assert_eq!(Ok(()), world.check_lengths());
+
+        world.table_mut::<Body>().mass.values.pop();
+        let errors = world.check_lengths().unwrap_err();
+        assert_eq!(vec![("mass", 0)], errors[0].columns);
+    }
+
+    #[test]
+    fn direct_creates_flush_reserved_rows_first() {
+        let mut world = World::new();
+        world.register::<Body>();
+
+        let reserved = world.reserve::<Body>(1.0);
+        let created = world.alloc_mut::<Body>().create();
+        world.table_mut::<Body>().insert_row(created, 2.0);
+
+        assert_eq!(Some(&1.0), world.table::<Body>().mass.get(reserved));
+        assert_eq!(Some(&2.0), world.table::<Body>().mass.get(created));
+        assert_eq!(Ok(()), world.check_lengths());
+    }
+
+    #[test]
+    fn conflicting_borrows_are_reported() {
+        let mut world = World::new();
+        world.register::<Body>();
+
+        let body = world.table::<Body>();
+        assert!(world.try_get::<Body>().is_ok());
+        assert_eq!(Some(WorldError::Borrowed(type_name::<Body>())), world.try_get_mut::<Body>().err());
+        drop(body);
+
+        assert!(world.try_get_mut::<Body>().is_ok());
+        assert_eq!(Some(WorldError::Missing(type_name::<Time>())), world.try_get::<Time>().err());
+    }
+}
@@ -0,0 +1,156 @@
+#![cfg(feature = "derive")]
+
+use simd_ecs::prelude::*;
+
+#[derive(Debug, Clone, Table)]
+pub struct BodyRow {
+    pub name: String,
+    pub mass: f64,
+}
+
+#[derive(Debug, Clone, Table)]
+#[table(Ship)]
+pub struct ShipTemplate {
+    pub speed: f32,
+}
+
+#[derive(Debug, Clone, Table)]
+#[table(clone)]
+pub struct NodeRow {
+    pub name: &'static str,
+    #[table(link)]
+    pub parent: Option<GenId<Node>>,
+}
+
+#[derive(Table)]
+pub struct LockRow {
+    pub count: std::sync::Mutex<u32>,
+}
+
+pub struct Handle;
+
+#[derive(Table)]
+pub struct OpaqueRow {
+    pub value: Handle,
+}
+
+fn row(name: &str, mass: f64) -> BodyRow {
+    BodyRow {
+        name: name.to_string(),
+        mass,
+    }
+}
+
+#[test]
+fn create_fixed() {
+    let mut alloc = FixedAllocator::<Body>::default();
+    let mut body = Body::default();
+
+    let earth = body.create(&mut alloc, row("Earth", 1.0));
+    let moon = body.create(&mut alloc, row("Luna", 0.01));
+
+    assert_eq!("Luna", body.get_row(moon).unwrap().name);
+    assert_eq!(1.0, *body.get_row(earth).unwrap().mass);
+
+    *body.get_row_mut(earth).unwrap().mass = 2.0;
+
+    let masses = body.iter_rows().map(|row| *row.mass).collect::<Vec<_>>();
+    assert_eq!(vec![2.0, 0.01], masses);
+}
+
+#[test]
+fn create_dynamic() {
+    let mut alloc = DynamicAllocator::<Body>::default();
+    let mut body = Body::default();
+
+    let a = body.create(&mut alloc, row("A", 1.0));
+    let b = body.create(&mut alloc, row("B", 2.0));
+    alloc.kill(a);
+
+    let rows = body.iter_rows_with_ids(&alloc)
+        .map(|(id, row)| (id, row.name.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(vec![(b, "B")], rows);
+}
+
+#[test]
+fn create_compacting() {
+    let mut alloc = DynamicAllocator::<Body>::compacting();
+    let mut body = Body::default();
+
+    let a = body.create(&mut alloc, row("A", 1.0));
+    let b = body.create(&mut alloc, row("B", 2.0));
+    alloc.kill_and_remove(a, &mut body);
+
+    assert_eq!(1, body.name.len());
+    assert_eq!("B", body.get_row(alloc.row(&b).unwrap()).unwrap().name);
+    assert!(body.get_row_for(&alloc, &a).is_none());
+
+    *body.get_row_mut_for(&alloc, &b).unwrap().mass = 3.0;
+    assert_eq!(3.0, *body.get_row_for(&alloc, &b).unwrap().mass);
+}
+
+#[test]
+fn named_table() {
+    let mut alloc = FixedAllocator::<Ship>::default();
+    let mut ship = Ship::default();
+
+    let id = ship.create(&mut alloc, ShipTemplate { speed: 3.0 });
+    assert_eq!(3.0, *ship.get_row(id).unwrap().speed);
+}
+
+#[test]
+fn rows_need_not_be_clone() {
+    let mut alloc = FixedAllocator::<Lock>::default();
+    let mut lock = Lock::default();
+
+    let id = lock.create(&mut alloc, LockRow { count: std::sync::Mutex::new(1) });
+    assert_eq!(1, *lock.get_row(id).unwrap().count.lock().unwrap());
+    assert!(lock.clone_row(id).is_none());
+}
+
+#[test]
+fn rows_need_not_be_debug() {
+    let mut alloc = FixedAllocator::<Opaque>::default();
+    let mut table = Opaque::default();
+    let id = table.create(&mut alloc, OpaqueRow { value: Handle });
+    assert!(table.get_row(id).is_some());
+
+    let mut body = Body::default();
+    let id = body.create(&mut FixedAllocator::default(), row("Earth", 1.0));
+    assert!(format!("{:?}", body.get_row(id).unwrap()).starts_with("BodyRowRef { name: \"Earth\""));
+    assert!(format!("{:?}", body).starts_with("Body { name: Comp1"));
+}
+
+#[test]
+fn check_lengths_reports_short_columns() {
+    let mut alloc = FixedAllocator::<Body>::default();
+    let mut body = Body::default();
+
+    body.create(&mut alloc, row("Earth", 1.0));
+    body.create(&mut alloc, row("Mars", 0.1));
+    assert_eq!(Ok(()), body.check_lengths(&alloc));
+
+    body.mass.values.pop();
+    let error = body.check_lengths(&alloc).unwrap_err();
+    assert_eq!(2, error.rows);
+    assert_eq!(vec![("mass", 1)], error.columns);
+    assert!(error.to_string().ends_with("has 2 rows but column `mass` has 1"));
+}
+
+#[test]
+fn clone_remaps_links() {
+    let mut alloc = DynamicAllocator::<Node>::default();
+    let mut node = Node::default();
+
+    let root = node.create(&mut alloc, NodeRow { name: "root", parent: None });
+    let leaf = node.create(&mut alloc, NodeRow { name: "leaf", parent: Some(root) });
+
+    let copy = node.clone_entity(&mut alloc, leaf).unwrap();
+    assert_eq!(Some(root), *node.get_row_for(&alloc, &copy).unwrap().parent);
+
+    let clones = Prefab::capture(&node, &alloc, vec![root, leaf]).unwrap().instantiate(&mut node, &mut alloc);
+    let (new_root, new_leaf) = (clones.get(&root).unwrap(), clones.get(&leaf).unwrap());
+    assert_eq!(Some(new_root), *node.get_row_for(&alloc, &new_leaf).unwrap().parent);
+    assert_eq!(Ok(()), node.check_lengths(&alloc));
+}